@@ -1,30 +1,64 @@
+import numpy as np
 from scipy.sparse import csr_matrix
 
 from .types import Neighbors, Labels
 from .spatial_de import somde
-from .spatialtis_core import build_neighbors_matrix
+from .spatialtis_core import build_neighbors_matrix, build_neighbors_matrix_csr
 # import rust mod as naive function, document in .pyi
 from .cell_interaction import (neighbor_components, CellCombs, comb_bootstrap)
+from .neighbors_graph import (neighbors_symmetric, neighbors_symmetric_parallel,
+                              neighbors_remove_self, neighbors_remove_self_parallel,
+                              neighbors_cap_degree, neighbors_cap_degree_parallel,
+                              neighbors_khop, neighbors_khop_parallel,
+                              neighbors_khop_with_hops, neighbors_khop_with_hops_parallel)
 
 # import python side function
-from .io import dumps_points_wkt, dumps_polygons_wkt, reads_wkt_points, reads_wkt_polygons
-from .neighbors import points_neighbors, bbox_neighbors
-from .shape_ops import points_bbox, multipoints_bbox, points_shapes, polygons_area, multipolygons_area
+from .io import dumps_points_wkt, dumps_polygons_wkt, reads_wkt_points, reads_wkt_polygons, \
+    reads_wkt_multipolygons
+from .neighbors import points_neighbors, points_neighbors_csr, points_neighbors_bipartite, \
+    points_neighbors_bipartite_parallel, bbox_neighbors, polygons_neighbors, polygons_neighbors_parallel, \
+    voronoi_neighbors, KDTree2D, KDTree3D
+from .shape_ops import points_bbox, multipoints_bbox, points_shapes, polygons_area, multipolygons_area, \
+    voronoi_shapes, polygons_morphology, polygons_centroid, polygons_interior_point, \
+    points_in_regions, points_signed_distance, convex_hull_3d, hull_volume_area, polygons_simplify, polygons_smooth
 from .geo_analysis import spatial_autocorr, spatial_distribution_pattern, spatial_entropy, getis_ord
 from .spatial_analysis import fast_corr
+from .preprocessing import mask2cell, mask_contours, cell_intensity, rasterize_polygons
 
 
 def spatial_weight(neighbors: Neighbors, labels: Labels) -> csr_matrix:
     """Build a neighbors sparse matrix from neighbors data
 
+    The i-th row and the i-th column of the matrix are the cell of `labels[i]`, so the labels
+    can be any unique integers, they don't need to be consecutive or start from 0.
+    Duplicated labels raise ValueError.
+
     Args:
         neighbors: List of neighbors
-        labels: List of neighbors
+        labels: List of labels, must be unique
 
     Return:
         A scipy sparse matrix in csr format
 
     """
     shape_n, indptr, col_index, row_index, data = build_neighbors_matrix(neighbors, labels)
-    return csr_matrix((data, col_index, indptr))
+    return csr_matrix((data, col_index, indptr), shape=(shape_n, shape_n))
+
+
+def spatial_weight_csr(indptr: np.ndarray, indices: np.ndarray, labels: np.ndarray) -> csr_matrix:
+    """Build a neighbors sparse matrix from neighbors in CSR format
+
+    Args:
+        indptr: The indptr return from :func:`points_neighbors_csr`, can be a slice of rows
+        indices: The indices return from :func:`points_neighbors_csr`
+        labels: List of labels, the i-th row and column of the matrix is the cell of `labels[i]`
 
+    Return:
+        A scipy sparse matrix in csr format
+
+    """
+    indptr = np.asarray(indptr, dtype=np.int64)
+    indices = np.asarray(indices, dtype=np.int64)
+    labels = np.asarray(labels, dtype=np.int64)
+    shape_n, indptr, col_index, data = build_neighbors_matrix_csr(indptr, indices, labels)
+    return csr_matrix((data, col_index, indptr), shape=(shape_n, shape_n))
@@ -2,8 +2,10 @@ from typing import List
 
 import numpy as np
 
-from .spatialtis_core import points_wkt, wkt_points, polygons_wkt, wkt_polygons
+from .spatialtis_core import (points_wkt, wkt_points, polygons_wkt, wkt_polygons,
+                              polygons_rings_wkt, wkt_polygons_rings, multipolygons_wkt, wkt_multipolygons)
 from .types import Points
+from .utils import polygon_depth, as_multipolygon, as_polygon_rings
 
 
 def dumps_points_wkt(points: Points) -> List[str]:
@@ -37,6 +39,10 @@ def reads_wkt_points(wkt_strings: List[str]) -> Points:
 def dumps_polygons_wkt(polygons: List[Points]) -> List[str]:
     """Dumps points into wkt format
 
+    A polygon can be a list of points, or a list of rings with holes where the first ring
+    is the exterior, which are dumped as POLYGON. If any of them is a multipolygon
+    (a list of polygons with holes), all are dumped as MULTIPOLYGON.
+
     Args:
         polygons: A list of polygons
 
@@ -44,17 +50,44 @@ def dumps_polygons_wkt(polygons: List[Points]) -> List[str]:
         A list of wkt string
 
     """
-    return polygons_wkt(polygons)
+    depth = max(polygon_depth(p) for p in polygons)
+    if depth == 2:
+        return polygons_wkt(polygons)
+    elif depth == 3:
+        return polygons_rings_wkt([as_polygon_rings(p) for p in polygons])
+    return multipolygons_wkt([as_multipolygon(p) for p in polygons])
 
 
-def reads_wkt_polygons(wkt_strings: List[str]) -> List[Points]:
+def reads_wkt_polygons(wkt_strings: List[str], holes: bool = False) -> List[Points]:
     """Reads wkt points into python object
 
+    Only the exterior of polygons is read by default, set `holes` to True to read
+    each polygon as a list of rings, the first ring is the exterior and the rest are the holes.
+
     Args:
         wkt_strings: A list of wkt string represents polygons
+        holes: Whether to read the holes
 
     Returns:
         A list of 2D polygons
 
     """
+    if holes:
+        return wkt_polygons_rings(wkt_strings)
     return wkt_polygons(wkt_strings)
+
+
+def reads_wkt_multipolygons(wkt_strings: List[str]) -> List[List[List[Points]]]:
+    """Reads wkt polygons and multipolygons into python object
+
+    Each geometry is read as a multipolygon, a list of polygons with holes,
+    a POLYGON is a multipolygon with one polygon.
+
+    Args:
+        wkt_strings: A list of wkt string represents polygons or multipolygons
+
+    Returns:
+        A list of multipolygons
+
+    """
+    return wkt_multipolygons(wkt_strings)
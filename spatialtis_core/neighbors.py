@@ -1,4 +1,6 @@
-from typing import Optional, List
+from typing import Optional, List, Union
+
+import numpy as np
 
 from .spatialtis_core import (points_neighbors_kdtree,
                               points_neighbors_kdtree_3d,
@@ -7,16 +9,53 @@ from .spatialtis_core import (points_neighbors_kdtree,
                               points_neighbors_kdtree_parallel,
                               points_neighbors_kdtree_3d_parallel,
                               points_neighbors_triangulation_parallel,
-                              bbox_neighbors_rtree_parallel
+                              bbox_neighbors_rtree_parallel,
+                              points_neighbors_kdtree_with_dist,
+                              points_neighbors_kdtree_3d_with_dist,
+                              points_neighbors_kdtree_with_dist_parallel,
+                              points_neighbors_kdtree_3d_with_dist_parallel,
+                              points_neighbors_gabriel,
+                              points_neighbors_gabriel_parallel,
+                              points_neighbors_relative,
+                              points_neighbors_relative_parallel,
+                              points_neighbors_triangulation_3d,
+                              points_neighbors_triangulation_3d_parallel,
+                              points_neighbors_triangulation_pruned,
+                              points_neighbors_triangulation_pruned_parallel,
+                              points_neighbors_kdtree_bipartite,
+                              points_neighbors_kdtree_3d_bipartite,
+                              points_neighbors_kdtree_bipartite_parallel,
+                              points_neighbors_kdtree_3d_bipartite_parallel,
+                              points_neighbors_kdtree_csr,
+                              points_neighbors_triangulation_csr,
+                              points_neighbors_grid_csr,
+                              points_neighbors_kdtree_adaptive,
+                              points_neighbors_kdtree_3d_adaptive,
+                              points_neighbors_kdtree_adaptive_parallel,
+                              points_neighbors_kdtree_3d_adaptive_parallel,
+                              polygons_neighbors_contact,
+                              polygons_neighbors_contact_parallel,
+                              polygons_neighbors_contact_with_border,
+                              polygons_neighbors_contact_with_border_parallel,
+                              points_voronoi_neighbors,
+                              points_voronoi_neighbors_parallel,
+                              KDTree2D,
+                              KDTree3D,
                               )
 from .types import Points, Labels, BoundingBox
+from .utils import show_options
 
 
 def points_neighbors(points: Points,
                      labels: Labels,
-                     r: Optional[float] = None,
+                     r: Union[float, List[float], None] = None,
                      k: Optional[int] = None,
                      method: str = "kdtree",
+                     return_distance: bool = False,
+                     max_edge: Optional[float] = None,
+                     edge_quantile: Optional[float] = None,
+                     remove_hull_slivers: bool = False,
+                     radius_mode: str = "either",
                      ) -> List[List[int]]:
     """Get neighbors for each points
 
@@ -25,25 +64,52 @@ def points_neighbors(points: Points,
     search for the 5 nearest neighbors; If combined, r = 5 and k = 5, it will
     search for at most 5 neighbors within 5;
 
-    When search with Delaunay triangulation, there is no parameter.
+    When search with Delaunay triangulation, long edges can be removed by `max_edge` or
+    `edge_quantile`, for example, edge_quantile = 0.99 will remove the longest 1% edges.
+    The hull edges of sliver triangles at the border can be removed by `remove_hull_slivers`.
+    For 3D points, the delaunay tetrahedralization is used and the pruning is not supported,
+    duplicated points raise a ValueError.
+
+    The "gabriel" (Gabriel graph) and "relative" (relative neighborhood graph) are subgraphs
+    of the delaunay triangulation with fewer edges, only available for 2D points.
+
+    The `r` can also be a radius for each point, for example, derived from the cell size.
+    With `radius_mode` = "either", two points are neighbors if either one reaches the other;
+    With "both", two points must reach each other. Only available for KD-tree without `k`.
 
     The return list follow the order of labels. For example, if serach for points of
     [100, 101, 102], the result may look like [[100, 102], [101, 102], [102, 100, 101]]
 
+    If `return_distance` is True, the euclidean distance to each neighbor is also returned,
+    the order of distances match the order of neighbors. Only available for KD-tree.
+
     Args:
         points: A list of points
         labels: Integer to labels your points
-        r: Radius range to search for neighbors
+        r: Radius range to search for neighbors, or a list of radius for each point
         k: Number of nearest neighbors
-        method: "kdtree" or "delaunay"
+        method: "kdtree", "delaunay", "gabriel" or "relative"
+        return_distance: Whether to return the distance of neighbors
+        max_edge: Remove delaunay edges longer than this value
+        edge_quantile: Remove delaunay edges longer than this quantile of edges length
+        remove_hull_slivers: Remove delaunay hull edges of sliver triangles
+        radius_mode: "either" or "both", only used when `r` is a list
 
     Return:
-         A list of neighbors
+         A list of neighbors, or (neighbors, distances) if `return_distance` is True
 
     """
     # Determine the dimension of points
     dims = len(points[0])
 
+    if (r is not None) & (not np.isscalar(r)):
+        if (method != "kdtree") | (k is not None) | return_distance:
+            raise ValueError("Radius for each point is only available for method='kdtree' without `k` and distance")
+        if dims == 2:
+            return points_neighbors_kdtree_adaptive(points, labels, r, radius_mode)
+        elif dims == 3:
+            return points_neighbors_kdtree_3d_adaptive(points, labels, r, radius_mode)
+
     if (r is None) & (k is None):
         k = 5
     elif r is None:
@@ -51,12 +117,34 @@ def points_neighbors(points: Points,
     elif k is None:
         k = 0
 
+    if return_distance & (method != "kdtree"):
+        raise ValueError("`return_distance` is only available for method='kdtree'")
+
     if dims == 2:
         if method == "kdtree":
+            if return_distance:
+                return points_neighbors_kdtree_with_dist(points, labels, r, k)
             return points_neighbors_kdtree(points, labels, r, k)
+        elif method == "gabriel":
+            return points_neighbors_gabriel(points, labels)
+        elif method == "relative":
+            return points_neighbors_relative(points, labels)
+        elif method != "delaunay":
+            msg = show_options(method, ["kdtree", "delaunay", "gabriel", "relative"])
+            raise ValueError(msg)
+        elif (max_edge is not None) | (edge_quantile is not None) | remove_hull_slivers:
+            return points_neighbors_triangulation_pruned(points, labels, max_edge, edge_quantile,
+                                                         remove_hull_slivers)
         else:
             return points_neighbors_triangulation(points, labels)
     elif dims == 3:
+        if method == "delaunay":
+            return points_neighbors_triangulation_3d(points, labels)
+        elif method != "kdtree":
+            msg = show_options(method, ["kdtree", "delaunay"])
+            raise ValueError(msg)
+        if return_distance:
+            return points_neighbors_kdtree_3d_with_dist(points, labels, r, k)
         return points_neighbors_kdtree_3d(points, labels, r, k)
     else:
         raise NotImplementedError("Only support 2D and 3D data")
@@ -64,9 +152,14 @@ def points_neighbors(points: Points,
 
 def points_neighbors_parallel(points_collections: List[Points],
                               labels_collections: List[Labels],
-                              r: Optional[float] = None,
+                              r: Union[float, List[float], None] = None,
                               k: Optional[int] = None,
                               method: str = "kdtree",
+                              return_distance: bool = False,
+                              max_edge: Optional[float] = None,
+                              edge_quantile: Optional[float] = None,
+                              remove_hull_slivers: bool = False,
+                              radius_mode: str = "either",
                               ) -> List[List[int]]:
     """Get neighbors for each points
 
@@ -75,25 +168,52 @@ def points_neighbors_parallel(points_collections: List[Points],
     search for the 5 nearest neighbors; If combined, r = 5 and k = 5, it will
     search for at most 5 neighbors within 5;
 
-    When search with Delaunay triangulation, there is no parameter.
+    When search with Delaunay triangulation, long edges can be removed by `max_edge` or
+    `edge_quantile`, for example, edge_quantile = 0.99 will remove the longest 1% edges.
+    The hull edges of sliver triangles at the border can be removed by `remove_hull_slivers`.
+    For 3D points, the delaunay tetrahedralization is used and the pruning is not supported,
+    duplicated points raise a ValueError.
+
+    The "gabriel" (Gabriel graph) and "relative" (relative neighborhood graph) are subgraphs
+    of the delaunay triangulation with fewer edges, only available for 2D points.
+
+    The `r` can also be a radius for each point in each ROI, for example, derived from the cell size.
+    With `radius_mode` = "either", two points are neighbors if either one reaches the other;
+    With "both", two points must reach each other. Only available for KD-tree without `k`.
 
     The return list follow the order of labels. For example, if serach for points of
     [100, 101, 102], the result may look like [[100, 102], [101, 102], [102, 100, 101]]
 
+    If `return_distance` is True, the euclidean distance to each neighbor is also returned,
+    the order of distances match the order of neighbors. Only available for KD-tree.
+
     Args:
         points_collections: A list of points in ROIs
         labels_collections: Integer to labels your points
-        r: Radius range to search for neighbors
+        r: Radius range to search for neighbors, or a list of radius for each point in each ROI
         k: Number of nearest neighbors
-        method: "kdtree" or "delaunay"
+        method: "kdtree", "delaunay", "gabriel" or "relative"
+        return_distance: Whether to return the distance of neighbors
+        max_edge: Remove delaunay edges longer than this value
+        edge_quantile: Remove delaunay edges longer than this quantile of edges length
+        remove_hull_slivers: Remove delaunay hull edges of sliver triangles
+        radius_mode: "either" or "both", only used when `r` is a list
 
     Return:
-         A list of neighbors
+         A list of neighbors, or (neighbors, distances) if `return_distance` is True
 
     """
     # Determine the dimension of points
     dims = len(points_collections[0][0])
 
+    if (r is not None) & (not np.isscalar(r)):
+        if (method != "kdtree") | (k is not None) | return_distance:
+            raise ValueError("Radius for each point is only available for method='kdtree' without `k` and distance")
+        if dims == 2:
+            return points_neighbors_kdtree_adaptive_parallel(points_collections, labels_collections, r, radius_mode)
+        elif dims == 3:
+            return points_neighbors_kdtree_3d_adaptive_parallel(points_collections, labels_collections, r, radius_mode)
+
     if (r is None) & (k is None):
         k = 5
     elif r is None:
@@ -101,17 +221,179 @@ def points_neighbors_parallel(points_collections: List[Points],
     elif k is None:
         k = 0
 
+    if return_distance & (method != "kdtree"):
+        raise ValueError("`return_distance` is only available for method='kdtree'")
+
     if dims == 2:
         if method == "kdtree":
+            if return_distance:
+                return points_neighbors_kdtree_with_dist_parallel(points_collections, labels_collections, r, k)
             return points_neighbors_kdtree_parallel(points_collections, labels_collections, r, k)
+        elif method == "gabriel":
+            return points_neighbors_gabriel_parallel(points_collections, labels_collections)
+        elif method == "relative":
+            return points_neighbors_relative_parallel(points_collections, labels_collections)
+        elif method != "delaunay":
+            msg = show_options(method, ["kdtree", "delaunay", "gabriel", "relative"])
+            raise ValueError(msg)
+        elif (max_edge is not None) | (edge_quantile is not None) | remove_hull_slivers:
+            return points_neighbors_triangulation_pruned_parallel(points_collections, labels_collections,
+                                                                  max_edge, edge_quantile, remove_hull_slivers)
         else:
             return points_neighbors_triangulation_parallel(points_collections, labels_collections)
     elif dims == 3:
+        if method == "delaunay":
+            return points_neighbors_triangulation_3d_parallel(points_collections, labels_collections)
+        elif method != "kdtree":
+            msg = show_options(method, ["kdtree", "delaunay"])
+            raise ValueError(msg)
+        if return_distance:
+            return points_neighbors_kdtree_3d_with_dist_parallel(points_collections, labels_collections, r, k)
         return points_neighbors_kdtree_3d_parallel(points_collections, labels_collections, r, k)
     else:
         raise NotImplementedError("Only support 2D and 3D data")
 
 
+def points_neighbors_csr(points: np.ndarray,
+                         labels: Optional[np.ndarray] = None,
+                         r: Optional[float] = None,
+                         k: Optional[int] = None,
+                         method: str = "kdtree",
+                         return_distance: bool = False,
+                         tile_size: Optional[float] = None,
+                         ):
+    """Get neighbors for each points in CSR format
+
+    The points are read from numpy array directly, this is much faster than
+    :func:`points_neighbors` for large dataset. The `r` and `k` work the same as :func:`points_neighbors`.
+
+    For whole slide data with millions of cells, use `method="grid"`, the points are hashed
+    into a uniform grid and searched tile by tile in parallel, which gives the same results as the KD-tree
+    radius search with lower memory usage. Only `r` is supported for grid search.
+
+    The neighbors of the i-th point are `indices[indptr[i]:indptr[i + 1]]`,
+    which can be used to construct a scipy sparse matrix.
+
+    Args:
+        points: A 2D array of points, the shape is (n, 2) or (n, 3)
+        labels: Integer to labels your points, default is 0 to n - 1
+        r: Radius range to search for neighbors
+        k: Number of nearest neighbors
+        method: "kdtree", "grid" or "delaunay"
+        return_distance: Whether to return the distance of neighbors, only available for KD-tree and grid
+        tile_size: The side length of a tile for grid search, default is 64 * r
+
+    Return:
+         (indptr, indices, distances), the distances is None if `return_distance` is False
+
+    """
+    points = np.ascontiguousarray(points, dtype=np.float64)
+    if labels is None:
+        labels = np.arange(len(points), dtype=np.int64)
+    else:
+        labels = np.asarray(labels, dtype=np.int64)
+
+    if method == "kdtree":
+        if (r is None) & (k is None):
+            k = 5
+        elif r is None:
+            r = -1.0
+        elif k is None:
+            k = 0
+        return points_neighbors_kdtree_csr(points, labels, r, k, return_distance)
+    elif method == "grid":
+        if r is None:
+            raise ValueError("`r` is required for method='grid'")
+        if k is not None:
+            raise ValueError("`k` is not supported for method='grid'")
+        return points_neighbors_grid_csr(points, labels, r, tile_size, return_distance)
+    elif method != "delaunay":
+        msg = show_options(method, ["kdtree", "grid", "delaunay"])
+        raise ValueError(msg)
+    elif return_distance:
+        raise ValueError("`return_distance` is only available for method='kdtree' and method='grid'")
+    else:
+        return points_neighbors_triangulation_csr(points, labels)
+
+
+def points_neighbors_bipartite(points: Points,
+                               target_points: Points,
+                               target_labels: Labels,
+                               r: Optional[float] = None,
+                               k: Optional[int] = None,
+                               ) -> List[List[int]]:
+    """Get neighbors for each points from another set of points
+
+    The KD-tree is built on `target_points`, the neighbors of each
+    point in `points` are searched within the target points.
+    The `r` and `k` work the same as :func:`points_neighbors`.
+
+    Args:
+        points: A list of points to query
+        target_points: A list of points to search for neighbors
+        target_labels: Integer to labels your target points
+        r: Radius range to search for neighbors
+        k: Number of nearest neighbors
+
+    Return:
+         A list of neighbors, each neighbor is a label of target points
+
+    """
+    dims = len(points[0])
+
+    if (r is None) & (k is None):
+        k = 5
+    elif r is None:
+        r = -1.0
+    elif k is None:
+        k = 0
+
+    if dims == 2:
+        return points_neighbors_kdtree_bipartite(points, target_points, target_labels, r, k)
+    elif dims == 3:
+        return points_neighbors_kdtree_3d_bipartite(points, target_points, target_labels, r, k)
+    else:
+        raise NotImplementedError("Only support 2D and 3D data")
+
+
+def points_neighbors_bipartite_parallel(points_collections: List[Points],
+                                        target_points_collections: List[Points],
+                                        target_labels_collections: List[Labels],
+                                        r: Optional[float] = None,
+                                        k: Optional[int] = None,
+                                        ) -> List[List[List[int]]]:
+    """Get neighbors for each points from another set of points in ROIs
+
+    Args:
+        points_collections: A list of points to query in ROIs
+        target_points_collections: A list of points to search for neighbors in ROIs
+        target_labels_collections: Integer to labels your target points
+        r: Radius range to search for neighbors
+        k: Number of nearest neighbors
+
+    Return:
+         A list of neighbors for each ROI
+
+    """
+    dims = len(points_collections[0][0])
+
+    if (r is None) & (k is None):
+        k = 5
+    elif r is None:
+        r = -1.0
+    elif k is None:
+        k = 0
+
+    if dims == 2:
+        return points_neighbors_kdtree_bipartite_parallel(points_collections, target_points_collections,
+                                                          target_labels_collections, r, k)
+    elif dims == 3:
+        return points_neighbors_kdtree_3d_bipartite_parallel(points_collections, target_points_collections,
+                                                             target_labels_collections, r, k)
+    else:
+        raise NotImplementedError("Only support 2D and 3D data")
+
+
 def bbox_neighbors(bbox: List[BoundingBox],
                    labels: Labels,
                    expand: float = -1.0,
@@ -157,3 +439,98 @@ def bbox_neighbors_parallel(bbox_collections: List[List[BoundingBox]],
         expand = -1.0
 
     return bbox_neighbors_rtree_parallel(bbox_collections, labels, expand, scale)
+
+
+def polygons_neighbors(polygons: List[Points],
+                       labels: Labels,
+                       buffer: float = 0.0,
+                       return_border: bool = False,
+                       ):
+    """Get neighbors for each cell by the contact of cell shape
+
+    Two cells are neighbors when their polygons touch, overlap or
+    are within the buffer distance. An R-tree on the bounding box is used to find the candidates.
+
+    Args:
+        polygons: A list of polygon, each polygon is a list of points
+        labels: Integer to label your polygons
+        buffer: The distance allowed between two polygons to be neighbors
+        return_border: To return the length of the shared border,
+            which is the border of the cell that within buffer of the neighbor
+
+    Return:
+        A list of neighbors, if return_border, a list of shared border length is also returned
+
+    """
+    if return_border:
+        return polygons_neighbors_contact_with_border(polygons, labels, buffer)
+    return polygons_neighbors_contact(polygons, labels, buffer)
+
+
+def polygons_neighbors_parallel(polygons_collections: List[List[Points]],
+                                labels: List[Labels],
+                                buffer: float = 0.0,
+                                return_border: bool = False,
+                                ):
+    """Get neighbors for each cell by the contact of cell shape
+
+    Args:
+        polygons_collections: A list of polygons
+        labels: Integer to label your polygons
+        buffer: The distance allowed between two polygons to be neighbors
+        return_border: To return the length of the shared border
+
+    Return:
+        A list of neighbors for each polygons, if return_border, the shared border length is also returned
+
+    """
+    if return_border:
+        return polygons_neighbors_contact_with_border_parallel(polygons_collections, labels, buffer)
+    return polygons_neighbors_contact_parallel(polygons_collections, labels, buffer)
+
+
+def voronoi_neighbors(points: Points,
+                      labels: Labels,
+                      bbox: Optional[BoundingBox] = None,
+                      roi: Optional[Points] = None,
+                      ):
+    """Get neighbors for each point by the shared border of the voronoi cells
+
+    The cells are clipped as in :func:`voronoi_shapes`, two points are neighbors
+    when their cells share a border inside the ROI.
+
+    Args:
+        points: A list of points
+        labels: Integer to label your points
+        bbox: The bounding box (minx, miny, maxx, maxy) to clip the cells
+        roi: A polygon to clip the cells
+
+    Return:
+        A list of neighbors and a list of shared border length
+
+    """
+    if isinstance(points, np.ndarray):
+        points = points.tolist()
+    if isinstance(roi, np.ndarray):
+        roi = roi.tolist()
+    return points_voronoi_neighbors(points, labels, bbox, roi)
+
+
+def voronoi_neighbors_parallel(points_collections: List[Points],
+                               labels: List[Labels],
+                               bbox: Optional[List[BoundingBox]] = None,
+                               roi: Optional[List[Points]] = None,
+                               ):
+    """Get neighbors for each point by the shared border of the voronoi cells
+
+    Args:
+        points_collections: A list of points
+        labels: Integer to label your points
+        bbox: The bounding box to clip the cells for each points
+        roi: The polygon to clip the cells for each points
+
+    Return:
+        A list of neighbors and shared border length for each points
+
+    """
+    return points_voronoi_neighbors_parallel(points_collections, labels, bbox, roi)
@@ -0,0 +1,10 @@
+from .spatialtis_core import (neighbors_symmetric,
+                              neighbors_symmetric_parallel,
+                              neighbors_remove_self,
+                              neighbors_remove_self_parallel,
+                              neighbors_cap_degree,
+                              neighbors_cap_degree_parallel,
+                              neighbors_khop,
+                              neighbors_khop_parallel,
+                              neighbors_khop_with_hops,
+                              neighbors_khop_with_hops_parallel)
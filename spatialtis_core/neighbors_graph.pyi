@@ -0,0 +1,176 @@
+from typing import List, Optional, Tuple
+
+from .types import Neighbors, Labels
+
+
+def neighbors_symmetric(neighbors: Neighbors,
+                        labels: Labels,
+                        mode: str = "union",
+                        ) -> Neighbors:
+    """Make the neighbors relationship symmetric
+
+    Args:
+        neighbors: List of neighbors
+        labels: List of labels
+        mode: "union" or "intersection"; If "union", A is added as neighbor of B if B is neighbor of A;
+            If "intersection", only the mutual neighbors are kept
+
+    Return:
+        List of neighbors
+
+    """
+    ...
+
+
+def neighbors_symmetric_parallel(neighbors_collections: List[Neighbors],
+                                 labels_collections: List[Labels],
+                                 mode: str = "union",
+                                 ) -> List[Neighbors]:
+    """Make the neighbors relationship symmetric for multiple ROIs
+
+    Args:
+        neighbors_collections: List of neighbors in ROIs
+        labels_collections: List of labels in ROIs
+        mode: "union" or "intersection"
+
+    Return:
+        List of neighbors in ROIs
+
+    """
+    ...
+
+
+def neighbors_remove_self(neighbors: Neighbors, labels: Labels) -> Neighbors:
+    """Remove the cell itself from its neighbors
+
+    Args:
+        neighbors: List of neighbors
+        labels: List of labels
+
+    Return:
+        List of neighbors
+
+    """
+    ...
+
+
+def neighbors_remove_self_parallel(neighbors_collections: List[Neighbors],
+                                   labels_collections: List[Labels],
+                                   ) -> List[Neighbors]:
+    """Remove the cell itself from its neighbors for multiple ROIs
+
+    Args:
+        neighbors_collections: List of neighbors in ROIs
+        labels_collections: List of labels in ROIs
+
+    Return:
+        List of neighbors in ROIs
+
+    """
+    ...
+
+
+def neighbors_cap_degree(neighbors: Neighbors,
+                         max_degree: int,
+                         distances: Optional[List[List[float]]] = None,
+                         ) -> Neighbors:
+    """Keep at most `max_degree` neighbors for each cell
+
+    Args:
+        neighbors: List of neighbors
+        max_degree: The maximum number of neighbors
+        distances: The distance of each neighbor, if provided, the nearest neighbors are kept;
+            Otherwise, the first neighbors in the list are kept
+
+    Return:
+        List of neighbors
+
+    """
+    ...
+
+
+def neighbors_cap_degree_parallel(neighbors_collections: List[Neighbors],
+                                  max_degree: int,
+                                  distances_collections: Optional[List[List[List[float]]]] = None,
+                                  ) -> List[Neighbors]:
+    """Keep at most `max_degree` neighbors for each cell for multiple ROIs
+
+    Args:
+        neighbors_collections: List of neighbors in ROIs
+        max_degree: The maximum number of neighbors
+        distances_collections: The distance of each neighbor in ROIs
+
+    Return:
+        List of neighbors in ROIs
+
+    """
+    ...
+
+
+def neighbors_khop(neighbors: Neighbors, labels: Labels, k: int) -> Neighbors:
+    """Expand the neighbors to k hops, the neighbors of neighbors are 2 hops away
+
+    The cell itself is not included, the neighbors are ordered by hops.
+    The neighbors that are not in the labels are kept but not expanded.
+
+    Args:
+        neighbors: List of neighbors
+        labels: List of labels
+        k: The number of hops
+
+    Return:
+        List of neighbors
+
+    """
+    ...
+
+
+def neighbors_khop_parallel(neighbors_collections: List[Neighbors],
+                            labels_collections: List[Labels],
+                            k: int,
+                            ) -> List[Neighbors]:
+    """Expand the neighbors to k hops for multiple ROIs
+
+    Args:
+        neighbors_collections: List of neighbors in ROIs
+        labels_collections: List of labels in ROIs
+        k: The number of hops
+
+    Return:
+        List of neighbors in ROIs
+
+    """
+    ...
+
+
+def neighbors_khop_with_hops(neighbors: Neighbors, labels: Labels, k: int) -> Tuple[Neighbors, List[List[int]]]:
+    """Expand the neighbors to k hops, with the hops of each neighbor
+
+    Args:
+        neighbors: List of neighbors
+        labels: List of labels
+        k: The number of hops
+
+    Return:
+        List of neighbors and the hops of each neighbor
+
+    """
+    ...
+
+
+def neighbors_khop_with_hops_parallel(neighbors_collections: List[Neighbors],
+                                      labels_collections: List[Labels],
+                                      k: int,
+                                      ) -> List[Tuple[Neighbors, List[List[int]]]]:
+    """Expand the neighbors to k hops for multiple ROIs, with the hops of each neighbor
+
+    Args:
+        neighbors_collections: List of neighbors in ROIs
+        labels_collections: List of labels in ROIs
+        k: The number of hops
+
+    Return:
+        List of neighbors and hops in ROIs
+
+    """
+    ...
@@ -0,0 +1,145 @@
+from typing import Dict, List, Optional, Tuple
+
+import numpy as np
+import pandas as pd
+
+from .spatialtis_core import mask2cell as _mask2cell, cell_intensity as _cell_intensity, polygons_rasterize, \
+    mask_contours as _mask_contours
+from .types import Points
+from .utils import as_multipolygon
+
+
+def mask2cell(mask: np.ndarray) -> pd.DataFrame:
+    """Extract cells from a segmentation label mask
+
+    Each label except the background 0 is a cell. The pixel at (row, col) is
+    the point (x=col, y=row), the border is the outline of the cell on the pixel corners
+    (see :func:`mask_contours`) that can be used in the geometry functions and :func:`dumps_polygons_wkt`.
+    The border is a multipolygon with a part for each 4-connected piece of the label, so a label
+    split into pieces, including pieces that touch only by a corner, is still one cell.
+
+    Args:
+        mask: A 2D integer array of labels
+
+    Return:
+        A table with columns label, area, centroid, bbox, eccentricity and border,
+        sorted by label. The bbox is (minx, miny, maxx, maxy) of the pixel centers.
+
+    """
+    mask = np.ascontiguousarray(mask, dtype=np.int64)
+    if mask.ndim != 2:
+        raise ValueError(f"The mask must be a 2D array, got {mask.ndim}D array")
+    labels, area, centroid, bbox, eccentricity, border = _mask2cell(mask)
+    return pd.DataFrame({"label": labels,
+                         "area": area,
+                         "centroid": centroid,
+                         "bbox": bbox,
+                         "eccentricity": eccentricity,
+                         "border": border})
+
+
+def mask_contours(mask: np.ndarray,
+                  subpixel: bool = False,
+                  tolerance: Optional[float] = None,
+                  ) -> Tuple[List[int], List[List[List[Points]]]]:
+    """Trace the outline of each label in a mask
+
+    The outline follows the pixel edges with the cell on the right hand side, it's the outer
+    boundary of a 4-connected piece of the label, the holes are not traced. The outline of a label
+    is a multipolygon with a part for each piece, including pieces that touch only by a corner.
+
+    Args:
+        mask: A 2D integer array of labels, 0 is the background
+        subpixel: Use the midpoints of the pixel edges as vertices instead of the pixel corners,
+            same as the marching squares at level 0.5
+        tolerance: Simplify the outline with Douglas-Peucker algorithm if set
+
+    Return:
+        The labels and the outlines as multipolygons of closed rings, sorted by label as :func:`mask2cell`
+
+    """
+    mask = np.ascontiguousarray(mask, dtype=np.int64)
+    if mask.ndim != 2:
+        raise ValueError(f"The mask must be a 2D array, got {mask.ndim}D array")
+    return _mask_contours(mask, subpixel, tolerance)
+
+
+def cell_intensity(mask: np.ndarray,
+                   image: np.ndarray,
+                   channels: Optional[List[str]] = None,
+                   percentiles: Optional[List[float]] = None,
+                   exclude_border: bool = False,
+                   channel_axis: int = 0,
+                   ) -> Dict[str, pd.DataFrame]:
+    """Quantify the marker intensity of each cell
+
+    The cells are computed in parallel, the percentiles use linear interpolation as
+    :func:`numpy.percentile`. When `exclude_border` is True, the border pixels of cells are not
+    counted to avoid the signal spill over from the neighbor cells, a cell with only border pixels gets NaN.
+
+    Args:
+        mask: A 2D integer array of labels, 0 is the background
+        image: A multichannel image, the other two axes match the mask
+        channels: The name of channels, default is 0 to n - 1
+        percentiles: The percentiles in [0, 100] to compute
+        exclude_border: Whether to exclude the border pixels of cells
+        channel_axis: The axis of channels in image
+
+    Return:
+        A dict of tables (cells x channels) indexed by label, the keys are
+        "mean", "median", "sum", "max" and "p{q}" for each percentile q
+
+    """
+    mask = np.ascontiguousarray(mask, dtype=np.int64)
+    image = np.asarray(image, dtype=np.float64)
+    if image.ndim == 2:
+        image = image[np.newaxis]
+    else:
+        image = np.moveaxis(image, channel_axis, 0)
+    image = np.ascontiguousarray(image)
+    if percentiles is None:
+        percentiles = []
+    if channels is None:
+        channels = list(range(image.shape[0]))
+    elif len(channels) != image.shape[0]:
+        raise ValueError(f"The number of channels ({len(channels)}) does not match the image ({image.shape[0]})")
+
+    labels, mean, median, sum_, max_, pct = _cell_intensity(mask, image, list(percentiles), exclude_border)
+    index = pd.Index(labels, name="label")
+    tables = {name: pd.DataFrame(data, index=index, columns=channels)
+              for name, data in [("mean", mean), ("median", median), ("sum", sum_), ("max", max_)]}
+    for q, data in zip(percentiles, pct):
+        tables[f"p{q:g}"] = pd.DataFrame(data, index=index, columns=channels)
+    return tables
+
+
+def rasterize_polygons(polygons: List[Points],
+                       shape: Tuple[int, int],
+                       labels: Optional[List[int]] = None,
+                       origin: Tuple[float, float] = (0.0, 0.0),
+                       resolution: float = 1.0,
+                       overlap: str = "last",
+                       ) -> np.ndarray:
+    """Render polygons into a label mask
+
+    The pixel at (row, col) is at (origin_x + col * resolution, origin_y + row * resolution),
+    a pixel takes the label of the polygon that covers it, the background is 0.
+    With the default origin and resolution, this is the reverse of :func:`mask2cell`.
+
+    Args:
+        polygons: A list of polygons, each is a list of points, a list of rings with holes or a multipolygon
+        shape: The (rows, cols) of the mask
+        labels: The labels of polygons, default is 1 to n
+        origin: The position of the pixel (0, 0)
+        resolution: The size of a pixel
+        overlap: "last" to let the last polygon win on the overlapped pixels, "error" to raise an error
+
+    Return:
+        A 2D integer array
+
+    """
+    if labels is None:
+        labels = list(range(1, len(polygons) + 1))
+    # The parts of a multipolygon don't overlap, all the rings are rendered together by the even-odd rule
+    polygons = [[ring for part in as_multipolygon(p) for ring in part] for p in polygons]
+    return polygons_rasterize(polygons, list(labels), tuple(shape), list(origin), resolution, overlap)
@@ -1,4 +1,4 @@
-from typing import List, Sequence
+from typing import List, Optional, Sequence
 
 import numpy as np
 import pandas as pd
@@ -12,9 +12,30 @@ from .spatialtis_core import (_points_bbox,
                               multipolygons_concave,
                               multipolygons_convex,
                               concave,
-                              convex)
+                              convex,
+                              points_voronoi,
+                              polygons_morphology as _polygons_morphology,
+                              multipolygons_centroid,
+                              multipolygons_interior_point,
+                              points_in_regions as _points_in_regions,
+                              points_signed_distance as _points_signed_distance,
+                              distance_bands,
+                              _rings_area,
+                              _rings_bbox,
+                              _rings_centroid,
+                              _rings_interior_point,
+                              rings_convex,
+                              rings_concave,
+                              multipoints3d_convex_hull,
+                              multipoints3d_hull_volume_area,
+                              polygons_simplify as _polygons_simplify,
+                              polygons_smooth as _polygons_smooth)
 from .types import Points, BoundingBox
-from .utils import show_options
+from .utils import show_options, polygon_depth, as_multipolygon, as_polygon_rings
+
+MORPHOLOGY_FEATURES = ["area", "perimeter", "centroid_x", "centroid_y", "equivalent_diameter",
+                       "major_axis_length", "minor_axis_length", "orientation", "eccentricity",
+                       "solidity", "circularity", "extent"]
 
 
 def points_bbox(points: Points) -> BoundingBox:
@@ -38,6 +59,9 @@ def points_bbox(points: Points) -> BoundingBox:
 def multipoints_bbox(points_collections: Sequence[Points]) -> Sequence[BoundingBox]:
     """A utility function to return minimum bounding box list of polygons
 
+    The polygons with holes (a list of rings) and multipolygons (a list of polygons with holes)
+    are also accepted.
+
     Args:
         points_collections: List of 2d points collections
 
@@ -47,6 +71,8 @@ def multipoints_bbox(points_collections: Sequence[Points]) -> Sequence[BoundingB
     """
     if isinstance(points_collections, np.ndarray):
         points_collections = points_collections.tolist()
+    if any(polygon_depth(p) > 2 for p in points_collections):
+        return _rings_bbox([as_multipolygon(p) for p in points_collections])
     if len(points_collections[0][0]) == 2:
         return _multipoints_bbox(points_collections)
     else:
@@ -56,6 +82,9 @@ def multipoints_bbox(points_collections: Sequence[Points]) -> Sequence[BoundingB
 def polygons_area(points: Points) -> float:
     """Calculate the area of polygons using shoelace formula
 
+    The area of holes is excluded if the polygon is given as a list of rings,
+    the first ring is the exterior. A multipolygon is a list of such polygons.
+
     Args:
         points: A list of points represents a polygon
 
@@ -65,12 +94,16 @@ def polygons_area(points: Points) -> float:
     """
     if isinstance(points, np.ndarray):
         points = points.tolist()
+    if polygon_depth(points) > 2:
+        return _rings_area([as_multipolygon(points)])[0]
     return _polygon_area(points)
 
 
 def multipolygons_area(points_collections: Sequence[Points]) -> Sequence[float]:
     """Calculate the area of polygons using shoelace formula
 
+        The polygons with holes and multipolygons are accepted as in :func:`polygons_area`
+
         Args:
             points_collections: List of 2d points collections, represents a list of polygons
 
@@ -80,12 +113,16 @@ def multipolygons_area(points_collections: Sequence[Points]) -> Sequence[float]:
         """
     if isinstance(points_collections, np.ndarray):
         points_collections = points_collections.tolist()
+    if any(polygon_depth(p) > 2 for p in points_collections):
+        return _rings_area([as_multipolygon(p) for p in points_collections])
     return _multipolygons_area(points_collections)
 
 
 def points_shapes(polygons: List[Points], method: str = "convex", concavity: float = 1.5) -> Points:
     """Acquire multipoints (shapes) that describe the points
 
+    The polygons with holes and multipolygons are accepted, the hull covers all the parts.
+
     Args:
         polygons: A list of polygons
         method: "convex" or "concave"
@@ -98,6 +135,13 @@ def points_shapes(polygons: List[Points], method: str = "convex", concavity: flo
     if isinstance(polygons, (np.ndarray, pd.Series)):
         polygons = polygons.tolist()
 
+    if any(polygon_depth(p) > 2 for p in polygons):
+        polygons = [as_multipolygon(p) for p in polygons]
+        if method == "concave":
+            return rings_concave(polygons, concavity)
+        elif method == "convex":
+            return rings_convex(polygons)
+
     if method == "concave":
         return multipolygons_concave(polygons, concavity)
     elif method == "convex":
@@ -106,3 +150,233 @@ def points_shapes(polygons: List[Points], method: str = "convex", concavity: flo
         msg = show_options(method, ["concave", "convex"])
         raise ValueError(msg)
 
+
+
+def voronoi_shapes(points: Points,
+                   bbox: Optional[BoundingBox] = None,
+                   roi: Optional[Points] = None,
+                   ) -> List[Points]:
+    """The voronoi tessellation of points clipped by the ROI
+
+    If `roi` is provided, the cells are clipped by the ROI polygon;
+    Else if `bbox` is provided, the cells are clipped by the bounding box;
+    Otherwise, the bounding box of points will be used.
+
+    A concave ROI may split a cell into several parts, so the cells are multipolygons
+    when `roi` is provided, a cell that doesn't overlap the ROI has no part.
+
+    Args:
+        points: A list of points
+        bbox: The bounding box (minx, miny, maxx, maxy) to clip the cells
+        roi: A polygon to clip the cells
+
+    Return:
+        A list of polygons, each polygon is the voronoi cell of a point,
+        a list of multipolygons if `roi` is provided
+
+    """
+    if isinstance(points, np.ndarray):
+        points = points.tolist()
+    if isinstance(roi, np.ndarray):
+        roi = roi.tolist()
+    cells = points_voronoi(points, bbox, roi)
+    if roi is None:
+        return [cell[0][0] if cell else [] for cell in cells]
+    return cells
+
+
+def polygons_morphology(polygons: List[Points]) -> pd.DataFrame:
+    """Shape descriptors of polygons
+
+    The axis lengths, orientation and eccentricity are from the ellipse that has
+    the same second moments as the polygon, the orientation is the angle in radians
+    between the x-axis and the major axis. Solidity is the area divided by the convex hull area,
+    circularity is 4 * pi * area / perimeter ** 2, extent is the area divided by the bounding box area.
+
+    Args:
+        polygons: A list of polygons
+
+    Return:
+        A table with a row for each polygon, the columns are
+        area, perimeter, centroid_x, centroid_y, equivalent_diameter,
+        major_axis_length, minor_axis_length, orientation, eccentricity,
+        solidity, circularity and extent
+
+    """
+    if isinstance(polygons, (np.ndarray, pd.Series)):
+        polygons = polygons.tolist()
+    return pd.DataFrame(_polygons_morphology(polygons), columns=MORPHOLOGY_FEATURES)
+
+
+def polygons_centroid(polygons: List[Points]) -> Points:
+    """The centroid of polygons
+
+    For degenerate polygons with zero area, like a line or a self-intersecting polygon
+    whose parts cancel out, the centroid of the border line is used.
+
+    The polygons with holes and multipolygons are accepted as in :func:`polygons_area`,
+    the centroid of a multipolygon is the area-weighted centroid of its parts.
+
+    Args:
+        polygons: A list of polygons
+
+    Return:
+        A list of points
+
+    """
+    if isinstance(polygons, (np.ndarray, pd.Series)):
+        polygons = polygons.tolist()
+    if any(polygon_depth(p) > 2 for p in polygons):
+        return _rings_centroid([as_multipolygon(p) for p in polygons])
+    return multipolygons_centroid(polygons)
+
+
+def polygons_interior_point(polygons: List[Points]) -> Points:
+    """A representative point that guaranteed to be inside each polygon
+
+    The centroid of a concave polygon may fall outside the polygon, use this
+    when the point must lie in the cell or region. For self-intersecting polygons,
+    the inside is determined by the even-odd rule. The point is never inside a hole,
+    for a multipolygon, it's inside the part with the widest span.
+
+    Args:
+        polygons: A list of polygons
+
+    Return:
+        A list of points
+
+    """
+    if isinstance(polygons, (np.ndarray, pd.Series)):
+        polygons = polygons.tolist()
+    if any(polygon_depth(p) > 2 for p in polygons):
+        return _rings_interior_point([as_multipolygon(p) for p in polygons])
+    return multipolygons_interior_point(polygons)
+
+
+def polygons_simplify(polygons: List[Points], tolerance: float, method: str = "dp") -> List[Points]:
+    """Reduce the vertices of polygons
+
+    The polygons are simplified in parallel, the result never self-intersects:
+    for Douglas-Peucker, the removed vertices that would make the border cross are put back,
+    for Visvalingam-Whyatt, the topology preserving variant is used.
+
+    Args:
+        polygons: A list of polygons
+        tolerance: For "dp", the maximum distance from a removed vertex to the new border;
+            for "vw", the minimum triangle area of a vertex with its neighbors to keep it
+        method: "dp" (Douglas-Peucker) or "vw" (Visvalingam-Whyatt)
+
+    Return:
+        A list of closed polygons
+
+    """
+    if method not in ("dp", "vw"):
+        msg = show_options(method, ["dp", "vw"])
+        raise ValueError(msg)
+    if isinstance(polygons, (np.ndarray, pd.Series)):
+        polygons = polygons.tolist()
+    return _polygons_simplify(polygons, tolerance, method)
+
+
+def polygons_smooth(polygons: List[Points], iterations: int = 1) -> List[Points]:
+    """Smooth the border of polygons by Chaikin's corner cutting
+
+    Each iteration doubles the number of vertices, the iterations stop early for a polygon
+    if its border would self-intersect.
+
+    Args:
+        polygons: A list of polygons
+        iterations: The number of iterations, at most 10
+
+    Return:
+        A list of closed polygons
+
+    """
+    if isinstance(polygons, (np.ndarray, pd.Series)):
+        polygons = polygons.tolist()
+    return _polygons_smooth(polygons, iterations)
+
+
+def points_in_regions(points: Points, regions: List) -> List[List[int]]:
+    """Assign points to the regions that contain them
+
+    Each region is a polygon, given as a list of points, or a list of rings
+    where the first ring is the exterior and the rest are the holes.
+    A point on the border belongs to the region, so a point can be in several regions
+    or none of them.
+
+    Args:
+        points: A list of points, usually the cell centroids
+        regions: A list of polygons, for example, annotated tumor and stroma regions
+
+    Return:
+        The indices of regions that contain the point, for each point
+
+    """
+    if isinstance(points, np.ndarray):
+        points = points.tolist()
+    regions = [as_polygon_rings(r) for r in regions]
+    return _points_in_regions(points, regions)
+
+
+def points_signed_distance(points: Points,
+                           regions: List,
+                           bands: Optional[List[float]] = None,
+                           ):
+    """The signed distance from points to the nearest boundary of regions
+
+    The distance is negative inside a region and positive outside, the boundary
+    of holes also counts. If `bands` are given, the distances are also binned by
+    the increasing breaks, the band of a distance d is the number of breaks <= d.
+    For example, bands=[-50, 0, 50] gives 0 for deep inside, 1 for the inner margin,
+    2 for the outer margin and 3 for far outside.
+
+    Args:
+        points: A list of points, usually the cell centroids
+        regions: A list of polygons, each is a list of points or a list of rings with holes
+        bands: The breaks of distance bands
+
+    Return:
+        A list of distances, or (distances, bands) if `bands` is given
+
+    """
+    if isinstance(points, np.ndarray):
+        points = points.tolist()
+    regions = [as_polygon_rings(r) for r in regions]
+    distances = _points_signed_distance(points, regions)
+    if bands is not None:
+        return distances, distance_bands(distances, list(bands))
+    return distances
+
+
+def convex_hull_3d(points_collections: List[Points]) -> List[List[List[int]]]:
+    """The 3D convex hull of point clouds
+
+    Args:
+        points_collections: A list of 3D points, each is a cell or a region
+
+    Return:
+        The triangles of each hull as the index of points,
+        empty if there are less than 4 points or the points are coplanar
+
+    """
+    if isinstance(points_collections, np.ndarray):
+        points_collections = points_collections.tolist()
+    return multipoints3d_convex_hull(points_collections)
+
+
+def hull_volume_area(points_collections: List[Points]) -> List[tuple]:
+    """The volume and surface area of 3D convex hull of point clouds
+
+    The volume can be used as the real volume of a 3D tissue instead of its bounding box.
+
+    Args:
+        points_collections: A list of 3D points, each is a cell or a region
+
+    Return:
+        A list of (volume, surface area), (0, 0) if there is no hull
+
+    """
+    if isinstance(points_collections, np.ndarray):
+        points_collections = points_collections.tolist()
+    return multipoints3d_hull_volume_area(points_collections)
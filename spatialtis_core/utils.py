@@ -1,5 +1,7 @@
 from typing import List
 
+import numpy as np
+
 
 def show_options(input: str, options: List):
     return f"{input} not found, available options are {', '.join([str(i) for i in options])}"
@@ -15,3 +17,24 @@ def default_radius_3d(bbox, ratio=0.1):
     w, h, d = abs(bbox[3] - bbox[0]), abs(bbox[4] - bbox[1]), abs(bbox[5] - bbox[2])
     r = min([w, h, d]) * ratio
     return r
+
+
+def polygon_depth(polygon) -> int:
+    """2 for a list of points, 3 for a polygon with holes, 4 for a multipolygon"""
+    return 2 + np.ndim(polygon[0][0])
+
+
+def as_polygon_rings(polygon):
+    # A polygon without holes can be given as a list of points
+    if polygon_depth(polygon) == 2:
+        return [polygon]
+    return polygon
+
+
+def as_multipolygon(polygon):
+    depth = polygon_depth(polygon)
+    if depth == 2:
+        return [[polygon]]
+    elif depth == 3:
+        return [polygon]
+    return polygon
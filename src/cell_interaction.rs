@@ -4,10 +4,12 @@ use counter::Counter;
 use itertools::Itertools;
 use ndarray::{ArrayView2, s};
 use numpy::PyReadonlyArray2;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use rand::prelude::*;
 use rayon::prelude::*;
 
+use crate::utils::{check_neighbors, check_same_length};
 use crate::{mean_f, mean_u, py_kwarg, std_f, std_u, zscore2pvalue};
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -27,6 +29,9 @@ pub fn comb_bootstrap(
     times: usize,
 ) -> Result<PyObject, PyErr> {
     let exp_matrix: ArrayView2<bool> = exp_matrix.as_array();
+    check_same_length("markers", markers.len(), "rows of exp_matrix", exp_matrix.nrows())?;
+    check_same_length("labels", labels.len(), "columns of exp_matrix", exp_matrix.ncols())?;
+    check_neighbors(&neighbors, &labels)?;
     // let neighbors = remove_rep_neighbors(neighbors, &labels);
     let size = labels.len();
     let labels_mapper: HashMap<usize, usize> =
@@ -164,14 +169,27 @@ impl CellCombs {
         pval: Option<f64>,
         method: Option<&str>,
     ) -> PyResult<PyObject> {
-        let real_storage: &HashMap<(&str, &str), Vec<usize>> =
-            &self.real_storage.extract(py).unwrap();
-        let sim_storage: &HashMap<(&str, &str), Vec<f64>> = &self.sim_storage.extract(py).unwrap();
+        let real_storage: &HashMap<(&str, &str), Vec<usize>> = &self.real_storage.extract(py)?;
+        let sim_storage: &HashMap<(&str, &str), Vec<f64>> = &self.sim_storage.extract(py)?;
         // let order: bool = self.order;
 
         let times = py_kwarg(times, 1000);
         let pval = py_kwarg(pval, 0.05);
         let method = py_kwarg(method, "pval");
+        if (method != "pval") & (method != "zscore") {
+            return Err(PyValueError::new_err(format!(
+                "method must be 'pval' or 'zscore', got '{}'",
+                method
+            )));
+        }
+        check_same_length("types", types.len(), "labels", labels.len())?;
+        check_neighbors(&neighbors, &labels)?;
+        if let Some(t) = types.iter().find(|t| !real_storage.contains_key(&(**t, **t))) {
+            return Err(PyValueError::new_err(format!(
+                "The cell type '{}' is not found in the types used to create CellCombs",
+                t
+            )));
+        }
         //let ignore_self = py_kwarg(ignore_self, true);
         let type_counts: HashMap<&str, usize> = types
             .to_owned()
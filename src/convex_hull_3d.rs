@@ -0,0 +1,342 @@
+// 3D convex hull by Quickhull
+//
+// Start from a tetrahedron of four extreme points, the points inside it are discarded and
+// every other point is put in the conflict list of one face it can see. The farthest point of
+// a conflict list removes the faces it can see, found by walking across the edges from the face,
+// and the horizon of the visible region is connected to the point. The points of the removed
+// faces are assigned to the new faces or discarded if they are inside the hull.
+// The faces are oriented so that the interior is on the positive side of `orient3d`.
+use std::collections::{HashMap, HashSet};
+
+use crate::custom_type::Point3D;
+use crate::delaunay_3d::{cross, dot, sub};
+use crate::predicates::orient3d;
+
+// The tolerance of orientation relative to the cube of the extent of points
+const HULL_EPS: f64 = 1e-12;
+
+// The faces of the hull under construction, the removed faces are kept as dead
+struct Hull<'a> {
+    points: &'a [Point3D],
+    eps: f64,
+    faces: Vec<[usize; 3]>,
+    alive: Vec<bool>,
+    // The points that see the face
+    conflicts: Vec<Vec<usize>>,
+    // The face of each directed edge
+    edges: HashMap<(usize, usize), usize>,
+}
+
+impl<'a> Hull<'a> {
+    // Negative if the point is outside the face
+    fn orient(&self, face: usize, p: usize) -> f64 {
+        let [a, b, c] = self.faces[face];
+        orient3d(&self.points[a], &self.points[b], &self.points[c], &self.points[p])
+    }
+
+    fn sees(&self, face: usize, p: usize) -> bool {
+        self.orient(face, p) < -self.eps
+    }
+
+    fn add_face(&mut self, face: [usize; 3]) -> usize {
+        let id = self.faces.len();
+        let [a, b, c] = face;
+        for edge in [(a, b), (b, c), (c, a)] {
+            self.edges.insert(edge, id);
+        }
+        self.faces.push(face);
+        self.alive.push(true);
+        self.conflicts.push(vec![]);
+        id
+    }
+
+    fn remove_face(&mut self, face: usize) {
+        let [a, b, c] = self.faces[face];
+        for edge in [(a, b), (b, c), (c, a)] {
+            self.edges.remove(&edge);
+        }
+        self.alive[face] = false;
+    }
+
+    // Put the point in the conflict list of the first face it sees
+    fn assign(&mut self, faces: &[usize], p: usize) {
+        if let Some(f) = faces.iter().find(|f| self.sees(**f, p)) {
+            self.conflicts[*f].push(p);
+        }
+    }
+
+    // Add the farthest point in the conflict list of the face
+    fn expand(&mut self, face: usize) -> Vec<usize> {
+        let apex = *self.conflicts[face]
+            .iter()
+            .min_by(|a, b| self.orient(face, **a).total_cmp(&self.orient(face, **b)))
+            .unwrap();
+        let mut visible = vec![face];
+        let mut checked: HashSet<usize> = HashSet::from([face]);
+        let mut i = 0;
+        while i < visible.len() {
+            let [a, b, c] = self.faces[visible[i]];
+            i += 1;
+            for (u, v) in [(a, b), (b, c), (c, a)] {
+                let twin = self.edges[&(v, u)];
+                if checked.insert(twin) && self.sees(twin, apex) {
+                    visible.push(twin);
+                }
+            }
+        }
+        let visible_set: HashSet<usize> = visible.iter().copied().collect();
+        // The horizon is the edges whose twin belongs to an invisible face
+        let horizon: Vec<(usize, usize)> = visible
+            .iter()
+            .flat_map(|f| {
+                let [a, b, c] = self.faces[*f];
+                [(a, b), (b, c), (c, a)]
+            })
+            .filter(|(u, v)| !visible_set.contains(&self.edges[&(*v, *u)]))
+            .collect();
+        let orphans: Vec<usize> = visible
+            .iter()
+            .flat_map(|f| std::mem::take(&mut self.conflicts[*f]))
+            .filter(|p| *p != apex)
+            .collect();
+        for f in &visible {
+            self.remove_face(*f);
+        }
+        let new_faces: Vec<usize> = horizon
+            .into_iter()
+            .map(|(u, v)| self.add_face([u, v, apex]))
+            .collect();
+        for p in orphans {
+            self.assign(&new_faces, p);
+        }
+        new_faces
+    }
+}
+
+// Return the triangles of the hull as point index, None if the points are
+// less than 4 or all lie on a plane
+pub fn convex_hull_3d(points: &[Point3D]) -> Option<Vec<[usize; 3]>> {
+    if points.len() < 4 {
+        return None;
+    }
+    let extent = (0..3)
+        .map(|d| {
+            let (lo, hi) = points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+                (lo.min(p[d]), hi.max(p[d]))
+            });
+            hi - lo
+        })
+        .fold(0.0, f64::max);
+    let eps = HULL_EPS * extent.powi(3);
+
+    let [i0, i1, i2, i3] = initial_tetrahedron(points, eps)?;
+    let interior = [
+        (points[i0][0] + points[i1][0] + points[i2][0] + points[i3][0]) / 4.0,
+        (points[i0][1] + points[i1][1] + points[i2][1] + points[i3][1]) / 4.0,
+        (points[i0][2] + points[i1][2] + points[i2][2] + points[i3][2]) / 4.0,
+    ];
+    let mut hull = Hull {
+        points,
+        eps,
+        faces: vec![],
+        alive: vec![],
+        conflicts: vec![],
+        edges: HashMap::new(),
+    };
+    for [a, b, c] in [[i0, i1, i2], [i0, i1, i3], [i0, i2, i3], [i1, i2, i3]] {
+        if orient3d(&points[a], &points[b], &points[c], &interior) > 0.0 {
+            hull.add_face([a, b, c]);
+        } else {
+            hull.add_face([a, c, b]);
+        }
+    }
+    // The points inside the tetrahedron see no face and are discarded
+    for p in 0..points.len() {
+        if ![i0, i1, i2, i3].contains(&p) {
+            hull.assign(&[0, 1, 2, 3], p);
+        }
+    }
+
+    let mut pending: Vec<usize> = (0..4).collect();
+    while let Some(f) = pending.pop() {
+        if hull.alive[f] && !hull.conflicts[f].is_empty() {
+            pending.extend(hull.expand(f));
+        }
+    }
+    Some(
+        hull.faces
+            .into_iter()
+            .zip(hull.alive)
+            .filter(|(_, alive)| *alive)
+            .map(|(f, _)| f)
+            .collect(),
+    )
+}
+
+// The volume and surface area of a closed triangle mesh oriented as the hull
+pub fn hull_volume_area(points: &[Point3D], faces: &[[usize; 3]]) -> (f64, f64) {
+    let origin = points[faces[0][0]];
+    faces.iter().fold((0.0, 0.0), |(volume, area), [a, b, c]| {
+        let (a, b, c) = (&points[*a], &points[*b], &points[*c]);
+        let n = cross(&sub(b, a), &sub(c, a));
+        (
+            volume + orient3d(a, b, c, &origin) / 6.0,
+            area + dot(&n, &n).sqrt() / 2.0,
+        )
+    })
+}
+
+// Four points that span a tetrahedron with a large volume
+fn initial_tetrahedron(points: &[Point3D], eps: f64) -> Option<[usize; 4]> {
+    let farthest = |score: &dyn Fn(&Point3D) -> f64| -> (usize, f64) {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i, score(p)))
+            .fold((0, f64::NEG_INFINITY), |best, s| if s.1 > best.1 { s } else { best })
+    };
+    let (i0, _) = farthest(&|p| -p[0]);
+    let (i1, d1) = farthest(&|p| {
+        let d = sub(p, &points[i0]);
+        dot(&d, &d)
+    });
+    if d1 == 0.0 {
+        return None;
+    }
+    let line = sub(&points[i1], &points[i0]);
+    let (i2, d2) = farthest(&|p| {
+        let n = cross(&line, &sub(p, &points[i0]));
+        dot(&n, &n)
+    });
+    if d2 == 0.0 {
+        return None;
+    }
+    let (i3, d3) = farthest(&|p| orient3d(&points[i0], &points[i1], &points[i2], p).abs());
+    if d3 <= eps {
+        return None;
+    }
+    Some([i0, i1, i2, i3])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::convex_hull_3d::{convex_hull_3d, hull_volume_area};
+    use crate::custom_type::Point3D;
+    use crate::delaunay_3d::dot;
+    use crate::predicates::orient3d;
+
+    #[test]
+    fn test_convex_hull_3d() {
+        // A 2 x 2 x 2 cube with points inside
+        let mut points = vec![[1.0, 1.0, 1.0], [0.5, 1.5, 0.2]];
+        for x in [0.0, 2.0] {
+            for y in [0.0, 2.0] {
+                for z in [0.0, 2.0] {
+                    points.push([x, y, z]);
+                }
+            }
+        }
+        let faces = convex_hull_3d(&points).unwrap();
+        assert!(faces.iter().all(|f| !f.contains(&0) && !f.contains(&1)));
+        let (volume, area) = hull_volume_area(&points, &faces);
+        assert!((volume - 8.0).abs() < 1e-9);
+        assert!((area - 24.0).abs() < 1e-9);
+
+        // Coplanar points have no hull
+        let flat = vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]];
+        assert!(convex_hull_3d(&flat).is_none());
+
+        // Points on a unit sphere, all points are inside every face
+        let n = 500;
+        let points: Vec<[f64; 3]> = (0..n)
+            .map(|i| {
+                let z = 1.0 - 2.0 * (i as f64 + 0.5) / n as f64;
+                let r = (1.0 - z * z).sqrt();
+                let theta = i as f64 * std::f64::consts::PI * (3.0 - 5.0_f64.sqrt());
+                [r * theta.cos(), r * theta.sin(), z]
+            })
+            .collect();
+        let faces = convex_hull_3d(&points).unwrap();
+        for [a, b, c] in &faces {
+            assert!(points
+                .iter()
+                .all(|p| orient3d(&points[*a], &points[*b], &points[*c], p) > -1e-9));
+        }
+        let (volume, _) = hull_volume_area(&points, &faces);
+        assert!((volume - 4.0 / 3.0 * std::f64::consts::PI).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_convex_hull_3d_random() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut cloud = |n: usize| -> Vec<Point3D> {
+            (0..n)
+                .map(|_| [rng.gen(), rng.gen(), rng.gen()])
+                .collect()
+        };
+
+        // The faces of a small cloud are the triangles that have all other points on one side
+        for _ in 0..5 {
+            let points = cloud(40);
+            let n = points.len();
+            let mut expected = HashSet::new();
+            for i in 0..n {
+                for j in (i + 1)..n {
+                    for k in (j + 1)..n {
+                        let sides: Vec<f64> = (0..n)
+                            .filter(|p| ![i, j, k].contains(p))
+                            .map(|p| orient3d(&points[i], &points[j], &points[k], &points[p]))
+                            .collect();
+                        if sides.iter().all(|s| *s > 0.0) || sides.iter().all(|s| *s < 0.0) {
+                            expected.insert([i, j, k]);
+                        }
+                    }
+                }
+            }
+            let faces: HashSet<[usize; 3]> = convex_hull_3d(&points)
+                .unwrap()
+                .into_iter()
+                .map(|mut f| {
+                    f.sort_unstable();
+                    f
+                })
+                .collect();
+            assert_eq!(faces, expected);
+        }
+
+        // A large cloud, the hull is closed, contains all the points, and the farthest
+        // point in any direction is a vertex of the hull
+        let points = cloud(50_000);
+        let faces = convex_hull_3d(&points).unwrap();
+        let edges: HashSet<(usize, usize)> = faces
+            .iter()
+            .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+            .collect();
+        assert_eq!(edges.len(), faces.len() * 3);
+        assert!(edges.iter().all(|(a, b)| edges.contains(&(*b, *a))));
+        for [a, b, c] in &faces {
+            assert!(points
+                .iter()
+                .all(|p| orient3d(&points[*a], &points[*b], &points[*c], p) >= 0.0));
+        }
+        let vertices: HashSet<usize> = faces.iter().flatten().copied().collect();
+        for _ in 0..200 {
+            let dir = [
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            ];
+            let farthest = (0..points.len())
+                .max_by(|i, j| dot(&points[*i], &dir).total_cmp(&dot(&points[*j], &dir)))
+                .unwrap();
+            assert!(vertices.contains(&farthest));
+        }
+        let (volume, _) = hull_volume_area(&points, &faces);
+        assert!((volume - 1.0).abs() < 0.01);
+    }
+}
@@ -6,6 +6,7 @@ use ndarray::parallel::prelude::*;
 use ndarray::prelude::*;
 use numpy::{PyArray1, PyReadonlyArray2, ToPyArray};
 use ordered_float::OrderedFloat;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -19,11 +20,12 @@ pub fn spearman_corr<'py>(
     py: Python<'py>,
     data1: PyReadonlyArray2<f64>,
     data2: PyReadonlyArray2<f64>,
-) -> &'py PyArray1<f64> {
+) -> PyResult<&'py PyArray1<f64>> {
     let data1: ArrayView2<f64> = data1.as_array();
     let data2: ArrayView2<f64> = data2.as_array();
+    check_shape(data1, data2)?;
 
-    pair2_spearman(data1, data2).to_pyarray(py)
+    Ok(pair2_spearman(data1, data2).to_pyarray(py))
 }
 
 #[pyfunction]
@@ -31,11 +33,29 @@ pub fn pearson_corr<'py>(
     py: Python<'py>,
     data1: PyReadonlyArray2<f64>,
     data2: PyReadonlyArray2<f64>,
-) -> &'py PyArray1<f64> {
+) -> PyResult<&'py PyArray1<f64>> {
     let data1: ArrayView2<f64> = data1.as_array();
     let data2: ArrayView2<f64> = data2.as_array();
+    check_shape(data1, data2)?;
 
-    pair2_pearson(data1, data2).to_pyarray(py)
+    Ok(pair2_pearson(data1, data2).to_pyarray(py))
+}
+
+fn check_shape(arr1: ArrayView2<f64>, arr2: ArrayView2<f64>) -> PyResult<()> {
+    if arr1.shape() != arr2.shape() {
+        return Err(PyValueError::new_err(format!(
+            "The shape of two input array does not match: {:?} and {:?}",
+            arr1.shape(),
+            arr2.shape()
+        )));
+    }
+    if arr1.ncols() == 0 {
+        return Err(PyValueError::new_err("The input arrays have no columns"));
+    }
+    if arr1.iter().chain(arr2.iter()).any(|a| a.is_nan()) {
+        return Err(PyValueError::new_err("The input arrays contain NaN"));
+    }
+    Ok(())
 }
 
 fn arr_mean_stack(arr: ArrayView2<f64>) -> (Array2<f64>, Array1<f64>) {
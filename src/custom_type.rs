@@ -2,3 +2,9 @@ pub(crate) type Point2D = [f64; 2];
 pub(crate) type Point3D = [f64; 3];
 pub(crate) type BBox = (f64, f64, f64, f64);
 pub(crate) type BBox3D = (f64, f64, f64, f64, f64, f64);
+pub(crate) type NeighborsDist = (Vec<Vec<usize>>, Vec<Vec<f64>>);
+pub(crate) type NeighborsHops = (Vec<Vec<usize>>, Vec<Vec<usize>>);
+// The exterior ring of a polygon followed by the interior rings (holes)
+pub(crate) type PolygonRings = Vec<Vec<Point2D>>;
+// The parts of a multipolygon, each part is a polygon with holes
+pub(crate) type MultiPolygonRings = Vec<PolygonRings>;
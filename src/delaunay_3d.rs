@@ -0,0 +1,574 @@
+// 3D delaunay tetrahedralization using the Bowyer-Watson algorithm
+//
+// The points are inserted one by one in the order of morton code, every insertion
+// starts walking from the last created tetrahedron to locate the point,
+// then all tetrahedrons whose circumsphere contains the point are removed,
+// and the cavity is re-filled with new tetrahedrons that connect to the point.
+//
+// Instead of a super tetrahedron, every hull face is closed by a ghost tetrahedron
+// with a vertex at infinity, so the hull edges are never lost. The predicates are exact,
+// degenerate inputs like the voxel grid get one of the valid tetrahedralizations.
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::PyResult;
+
+use crate::custom_type::Point3D;
+use crate::neighbors_search::triangulation_edges;
+use crate::predicates::{insphere, orient3d};
+
+// The vertex at infinity of ghost tetrahedrons
+const INFINITE: usize = usize::MAX;
+
+struct Tetrahedron {
+    vertices: [usize; 4],
+    // The neighbor opposite to each vertex
+    neighbors: [usize; 4],
+    alive: bool,
+}
+
+struct Tetrahedralization<'a> {
+    points: &'a [Point3D],
+    tets: Vec<Tetrahedron>,
+    free: Vec<usize>,
+    last: usize,
+}
+
+// Return the unique edges of the delaunay tetrahedralization as point index pairs,
+// the points on a plane are triangulated in 2D, duplicated points are not allowed
+pub fn tetrahedralize_edges(points: &[Point3D]) -> PyResult<Vec<(usize, usize)>> {
+    check_duplicates(points)?;
+    let order = morton_order(points);
+    let mut edges = match initial_tetrahedron(points, &order) {
+        Degenerate::Tetrahedron(first) => {
+            let mut t = Tetrahedralization::new(points, first);
+            for i in order {
+                if !first.contains(&i) {
+                    t.insert(i);
+                }
+            }
+            t.edges()
+        }
+        Degenerate::Plane(origin, u, v) => {
+            let projected: Vec<[f64; 2]> = points
+                .iter()
+                .map(|p| {
+                    let d = sub(p, &points[origin]);
+                    [dot(&d, &u), dot(&d, &v)]
+                })
+                .collect();
+            triangulation_edges(&projected, false)
+                .into_iter()
+                .map(|(p1, p2, _)| (p1.min(p2), p1.max(p2)))
+                .collect()
+        }
+        Degenerate::Line(origin, u) => {
+            let mut along: Vec<(f64, usize)> = (0..points.len())
+                .map(|i| (dot(&sub(&points[i], &points[origin]), &u), i))
+                .collect();
+            along.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            along
+                .windows(2)
+                .map(|w| (w[0].1.min(w[1].1), w[0].1.max(w[1].1)))
+                .collect()
+        }
+    };
+    edges.sort_unstable();
+    edges.dedup();
+    Ok(edges)
+}
+
+fn check_duplicates(points: &[Point3D]) -> PyResult<()> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|a, b| points[*a].partial_cmp(&points[*b]).unwrap());
+    match order.windows(2).find(|w| points[w[0]] == points[w[1]]) {
+        Some(w) => Err(PyValueError::new_err(format!(
+            "Duplicated points at index {} and {}",
+            w[0].min(w[1]),
+            w[0].max(w[1])
+        ))),
+        None => Ok(()),
+    }
+}
+
+enum Degenerate {
+    Tetrahedron([usize; 4]),
+    // A point on the plane and the orthonormal basis of the plane
+    Plane(usize, Point3D, Point3D),
+    // A point on the line and the direction of the line
+    Line(usize, Point3D),
+}
+
+// The first four points in order that are not coplanar
+fn initial_tetrahedron(points: &[Point3D], order: &[usize]) -> Degenerate {
+    let normalize = |p: Point3D| {
+        let norm = dot(&p, &p).sqrt();
+        if norm > 0.0 {
+            [p[0] / norm, p[1] / norm, p[2] / norm]
+        } else {
+            p
+        }
+    };
+    let i0 = match order.first() {
+        Some(i) => *i,
+        None => return Degenerate::Line(0, [1.0, 0.0, 0.0]),
+    };
+    let i1 = match order.get(1) {
+        Some(i) => *i,
+        None => return Degenerate::Line(i0, [1.0, 0.0, 0.0]),
+    };
+    let u = normalize(sub(&points[i1], &points[i0]));
+    // Three points are collinear if they are coplanar with any other point off the line
+    let offset = points[i0].iter().fold(1.0, |m: f64, x| m.max(x.abs()));
+    let off_line: Vec<Point3D> = (0..3)
+        .map(|d| {
+            let mut q = points[i0];
+            q[d] += offset;
+            q
+        })
+        .collect();
+    let i2 = order.iter().copied().find(|i| {
+        off_line
+            .iter()
+            .any(|q| orient3d(&points[i0], &points[i1], q, &points[*i]) != 0.0)
+    });
+    let i2 = match i2 {
+        Some(i) => i,
+        None => return Degenerate::Line(i0, u),
+    };
+    let i3 = order
+        .iter()
+        .copied()
+        .find(|i| orient3d(&points[i0], &points[i1], &points[i2], &points[*i]) != 0.0);
+    match i3 {
+        Some(i3) => Degenerate::Tetrahedron([i0, i1, i2, i3]),
+        None => {
+            let normal = cross(&u, &sub(&points[i2], &points[i0]));
+            Degenerate::Plane(i0, u, normalize(cross(&normal, &u)))
+        }
+    }
+}
+
+impl<'a> Tetrahedralization<'a> {
+    fn new(points: &'a [Point3D], first: [usize; 4]) -> Tetrahedralization<'a> {
+        let mut t = Tetrahedralization {
+            points,
+            tets: vec![],
+            free: vec![],
+            last: 0,
+        };
+        let mut vertices = first;
+        if t.orient(&vertices) < 0.0 {
+            vertices.swap(0, 1);
+        }
+        let mut all = vec![vertices];
+        // The ghost replaces a vertex with infinity on the other side of the face,
+        // swap two finite vertices to keep it positive oriented
+        for i in 0..4 {
+            let mut ghost = vertices;
+            ghost[i] = INFINITE;
+            let (a, b) = if i < 2 { (2, 3) } else { (0, 1) };
+            ghost.swap(a, b);
+            all.push(ghost);
+        }
+        let mut faces: HashMap<[usize; 3], (usize, usize)> = HashMap::new();
+        for vertices in all {
+            let ix = t.add_tet(vertices, [INFINITE; 4]);
+            for i in 0..4 {
+                let mut face = [0; 3];
+                let mut k = 0;
+                for (j, v) in vertices.iter().enumerate() {
+                    if j != i {
+                        face[k] = *v;
+                        k += 1;
+                    }
+                }
+                face.sort_unstable();
+                match faces.remove(&face) {
+                    Some((other, other_i)) => {
+                        t.tets[ix].neighbors[i] = other;
+                        t.tets[other].neighbors[other_i] = ix;
+                    }
+                    None => {
+                        faces.insert(face, (ix, i));
+                    }
+                }
+            }
+        }
+        t
+    }
+
+    fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = vec![];
+        for tet in self.tets.iter().filter(|tet| tet.alive) {
+            let v = tet.vertices;
+            for a in 0..4 {
+                for b in (a + 1)..4 {
+                    // The finite edges of ghosts are also in the finite tetrahedrons
+                    if (v[a] != INFINITE) & (v[b] != INFINITE) {
+                        edges.push((v[a].min(v[b]), v[a].max(v[b])));
+                    }
+                }
+            }
+        }
+        edges
+    }
+
+    fn add_tet(&mut self, vertices: [usize; 4], neighbors: [usize; 4]) -> usize {
+        let tet = Tetrahedron {
+            vertices,
+            neighbors,
+            alive: true,
+        };
+        match self.free.pop() {
+            Some(ix) => {
+                self.tets[ix] = tet;
+                ix
+            }
+            None => {
+                self.tets.push(tet);
+                self.tets.len() - 1
+            }
+        }
+    }
+
+    fn orient(&self, v: &[usize; 4]) -> f64 {
+        orient3d(
+            &self.points[v[0]],
+            &self.points[v[1]],
+            &self.points[v[2]],
+            &self.points[v[3]],
+        )
+    }
+
+    // The orientation of tetrahedron with its i-th vertex replaced by p
+    fn orient_replaced(&self, tet: usize, i: usize, p: usize) -> f64 {
+        let mut v = self.tets[tet].vertices;
+        v[i] = p;
+        self.orient(&v)
+    }
+
+    fn infinite_vertex(&self, tet: usize) -> Option<usize> {
+        self.tets[tet].vertices.iter().position(|v| *v == INFINITE)
+    }
+
+    // For a ghost, p conflicts if it's outside the hull face, or on the plane of
+    // the hull face and inside its circumcircle, which is the same as inside
+    // the circumsphere of the finite tetrahedron on the other side
+    fn in_conflict(&self, tet: usize, p: usize) -> bool {
+        match self.infinite_vertex(tet) {
+            Some(i) => {
+                let side = self.orient_replaced(tet, i, p);
+                (side > 0.0) || ((side == 0.0) && self.in_conflict(self.tets[tet].neighbors[i], p))
+            }
+            None => {
+                let v = self.tets[tet].vertices;
+                insphere(
+                    &self.points[v[0]],
+                    &self.points[v[1]],
+                    &self.points[v[2]],
+                    &self.points[v[3]],
+                    &self.points[p],
+                ) < 0.0
+            }
+        }
+    }
+
+    // Walk from the last tetrahedron to the one contains p, or the ghost whose
+    // hull face can be seen by p
+    fn locate(&self, p: usize) -> usize {
+        let mut current = self.last;
+        if let Some(i) = self.infinite_vertex(current) {
+            if self.in_conflict(current, p) {
+                return current;
+            }
+            current = self.tets[current].neighbors[i];
+        }
+        let max_steps = self.tets.len() + 1;
+        'walk: for _ in 0..max_steps {
+            for i in 0..4 {
+                if self.orient_replaced(current, i, p) < 0.0 {
+                    current = self.tets[current].neighbors[i];
+                    if self.infinite_vertex(current).is_some() {
+                        return current;
+                    }
+                    continue 'walk;
+                }
+            }
+            return current;
+        }
+        // The walk never cycles in a delaunay tetrahedralization, just in case
+        (0..self.tets.len())
+            .find(|t| self.tets[*t].alive && self.in_conflict(*t, p))
+            .unwrap()
+    }
+
+    fn insert(&mut self, p: usize) {
+        let seed = self.locate(p);
+
+        // Find all the tetrahedrons in conflict with p, they are connected
+        let mut bad = vec![seed];
+        let mut is_bad: HashMap<usize, bool> = HashMap::new();
+        is_bad.insert(seed, true);
+        let mut boundary = vec![];
+        let mut ptr = 0;
+        while ptr < bad.len() {
+            let t = bad[ptr];
+            ptr += 1;
+            for i in 0..4 {
+                let nb = self.tets[t].neighbors[i];
+                let inside = match is_bad.get(&nb) {
+                    Some(inside) => *inside,
+                    None => {
+                        let inside = self.in_conflict(nb, p);
+                        is_bad.insert(nb, inside);
+                        if inside {
+                            bad.push(nb);
+                        }
+                        inside
+                    }
+                };
+                if !inside {
+                    boundary.push((t, i, nb));
+                }
+            }
+        }
+
+        // Fill the cavity with new tetrahedrons, the cavity is star-shaped from p
+        let mut new_tets = Vec::with_capacity(boundary.len());
+        for (t, i, nb) in &boundary {
+            let mut vertices = self.tets[*t].vertices;
+            vertices[*i] = p;
+            let mut neighbors = [INFINITE; 4];
+            neighbors[*i] = *nb;
+            new_tets.push((vertices, neighbors, *t, *i, *nb));
+        }
+        for t in &bad {
+            self.tets[*t].alive = false;
+        }
+
+        // Each face contains p is shared by two new tetrahedrons
+        let mut faces: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        for (vertices, neighbors, old, i, nb) in new_tets {
+            let ix = self.add_tet(vertices, neighbors);
+            let slot = self.tets[nb].neighbors.iter().position(|x| *x == old);
+            if let Some(slot) = slot {
+                self.tets[nb].neighbors[slot] = ix;
+            }
+            for j in (0..4).filter(|j| *j != i) {
+                let others: Vec<usize> = (0..4)
+                    .filter(|x| (*x != i) & (*x != j))
+                    .map(|x| vertices[x])
+                    .collect();
+                let key = (others[0].min(others[1]), others[0].max(others[1]));
+                match faces.remove(&key) {
+                    Some((other, other_j)) => {
+                        self.tets[ix].neighbors[j] = other;
+                        self.tets[other].neighbors[other_j] = ix;
+                    }
+                    None => {
+                        faces.insert(key, (ix, j));
+                    }
+                }
+            }
+            self.last = ix;
+        }
+        // Only recycle after all the new tetrahedrons are linked,
+        // the neighbors are updated by looking for the removed index
+        self.free.extend(bad);
+    }
+}
+
+pub(crate) fn sub(a: &Point3D, b: &Point3D) -> Point3D {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub(crate) fn dot(a: &Point3D, b: &Point3D) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: &Point3D, b: &Point3D) -> Point3D {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// Sort the points by morton code, so that the consecutive points are spatially close
+fn morton_order(points: &[Point3D]) -> Vec<usize> {
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for p in points {
+        for d in 0..3 {
+            min[d] = min[d].min(p[d]);
+            max[d] = max[d].max(p[d]);
+        }
+    }
+    let codes: Vec<u64> = points
+        .iter()
+        .map(|p| {
+            let mut code: u64 = 0;
+            for d in 0..3 {
+                let span = max[d] - min[d];
+                let q = if span > 0.0 {
+                    ((p[d] - min[d]) / span * 1023.0) as u64
+                } else {
+                    0
+                };
+                for bit in 0..10 {
+                    code |= ((q >> bit) & 1) << (bit * 3 + d);
+                }
+            }
+            code
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by_key(|i| codes[*i]);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::custom_type::Point3D;
+    use crate::delaunay_3d::{
+        initial_tetrahedron, morton_order, tetrahedralize_edges, Degenerate, Tetrahedralization,
+        INFINITE,
+    };
+    use crate::predicates::insphere;
+
+    // Check the tetrahedralization is closed, positive oriented and every
+    // circumsphere is empty by brute force
+    fn check_delaunay(points: &[Point3D]) {
+        let order = morton_order(points);
+        let first = match initial_tetrahedron(points, &order) {
+            Degenerate::Tetrahedron(first) => first,
+            _ => panic!("degenerate points"),
+        };
+        let mut t = Tetrahedralization::new(points, first);
+        for i in order {
+            if !first.contains(&i) {
+                t.insert(i);
+            }
+        }
+        let alive: Vec<usize> = (0..t.tets.len()).filter(|i| t.tets[*i].alive).collect();
+        let mut volume = 0.0;
+        for ix in &alive {
+            let tet = &t.tets[*ix];
+            for (i, nb) in tet.neighbors.iter().enumerate() {
+                assert!(t.tets[*nb].alive);
+                let back = t.tets[*nb].neighbors.iter().position(|x| x == ix).unwrap();
+                let mut face: Vec<usize> = (0..4)
+                    .filter(|j| *j != i)
+                    .map(|j| tet.vertices[j])
+                    .collect();
+                let mut other: Vec<usize> = (0..4)
+                    .filter(|j| *j != back)
+                    .map(|j| t.tets[*nb].vertices[j])
+                    .collect();
+                face.sort_unstable();
+                other.sort_unstable();
+                assert_eq!(face, other);
+            }
+            if tet.vertices.contains(&INFINITE) {
+                continue;
+            }
+            let [a, b, c, d] = tet.vertices.map(|v| points[v]);
+            let orient = t.orient(&tet.vertices);
+            assert!(orient > 0.0);
+            volume += orient / 6.0;
+            assert!(points.iter().all(|e| insphere(&a, &b, &c, &d, e) >= 0.0));
+        }
+        // Every point is a vertex
+        let mut used = vec![false; points.len()];
+        for ix in &alive {
+            for v in t.tets[*ix].vertices.iter().filter(|v| **v != INFINITE) {
+                used[*v] = true;
+            }
+        }
+        assert!(used.iter().all(|u| *u));
+        assert!(volume > 0.0);
+    }
+
+    #[test]
+    fn test_tetrahedralize_edges() {
+        // A single tetrahedron
+        let points = vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let edges = tetrahedralize_edges(&points).unwrap();
+        assert_eq!(edges.len(), 6);
+
+        // A cube with a point in the center, the center connects to all corners
+        let mut points = vec![];
+        for x in [0.0, 1.0] {
+            for y in [0.0, 1.0] {
+                for z in [0.0, 1.0] {
+                    points.push([x, y, z]);
+                }
+            }
+        }
+        points.push([0.5, 0.5, 0.5]);
+        let edges = tetrahedralize_edges(&points).unwrap();
+        assert_eq!(edges.iter().filter(|(_, p2)| *p2 == 8).count(), 8);
+        // No edges cross the cube between opposite corners
+        assert!(!edges.contains(&(0, 7)));
+        // All the 12 edges of the cube are on the hull
+        for (a, b) in [(0, 1), (0, 2), (0, 4), (3, 7), (5, 7), (6, 7)] {
+            assert!(edges.contains(&(a, b)));
+        }
+
+        // Duplicated points are rejected
+        points.push([1.0, 1.0, 1.0]);
+        assert!(tetrahedralize_edges(&points).is_err());
+
+        // Coplanar and collinear points
+        let square = vec![
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [0.0, 1.0, 1.0],
+            [1.0, 1.0, 1.0],
+        ];
+        assert_eq!(tetrahedralize_edges(&square).unwrap().len(), 5);
+        let line = vec![[2.0, 2.0, 2.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]];
+        assert_eq!(tetrahedralize_edges(&line).unwrap(), vec![(0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn test_tetrahedralize_random() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for n in [5, 20, 300] {
+            let points: Vec<Point3D> = (0..n)
+                .map(|_| [rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>()])
+                .collect();
+            check_delaunay(&points);
+        }
+    }
+
+    #[test]
+    fn test_tetrahedralize_degenerate() {
+        // Voxel centers, all cospherical and coplanar
+        let mut grid = vec![];
+        for x in 0..5 {
+            for y in 0..5 {
+                for z in 0..5 {
+                    grid.push([x as f64 * 0.1, y as f64 * 0.1, z as f64 * 0.1]);
+                }
+            }
+        }
+        check_delaunay(&grid);
+        // Nearly cospherical, the grid jittered by a tiny amount
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let jittered: Vec<Point3D> = grid
+            .iter()
+            .map(|p| p.map(|x| x + rng.gen_range(-1e-10..1e-10)))
+            .collect();
+        check_delaunay(&jittered);
+    }
+}
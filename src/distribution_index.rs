@@ -1,6 +1,7 @@
 use itertools::Itertools;
 use kiddo::distance::squared_euclidean;
 use ndarray::Array1;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use rand::prelude::*;
 use rayon::prelude::*;
@@ -8,7 +9,7 @@ use rayon::prelude::*;
 use crate::custom_type::{BBox, BBox3D, Point2D, Point3D};
 use crate::neighbors_search::kdtree_builder;
 use crate::quad_stats::QuadStats;
-use crate::utils::{chisquare2pvalue, zscore2pvalue};
+use crate::utils::{check_points, chisquare2pvalue, zscore2pvalue};
 
 const EMPTY_RETURN: (f64, f64, usize) = (0.0, 0.0, 0);
 static SEED: u64 = 0;
@@ -25,6 +26,33 @@ pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     Ok(())
 }
 
+fn check_bbox(bbox: BBox) -> PyResult<()> {
+    if !((bbox.0 < bbox.2) & (bbox.1 < bbox.3)) {
+        return Err(PyValueError::new_err(format!(
+            "The bbox must be (minx, miny, maxx, maxy) with positive width and height, got {:?}",
+            bbox
+        )));
+    }
+    Ok(())
+}
+
+fn check_bbox_3d(bbox: BBox3D) -> PyResult<()> {
+    if !((bbox.0 < bbox.3) & (bbox.1 < bbox.4) & (bbox.2 < bbox.5)) {
+        return Err(PyValueError::new_err(format!(
+            "The bbox must be (minx, miny, minz, maxx, maxy, maxz) with positive extent, got {:?}",
+            bbox
+        )));
+    }
+    Ok(())
+}
+
+fn check_resample(resample: usize) -> PyResult<()> {
+    if resample == 0 {
+        return Err(PyValueError::new_err("resample must be larger than 0"));
+    }
+    Ok(())
+}
+
 fn bbox_side_part(bbox: BBox) -> (f64, f64) {
     // -> (min_side, max_side)
     let x_range = bbox.2 - bbox.0;
@@ -44,7 +72,7 @@ pub fn ix_dispersion_parallel(
     resample: usize,
     pval: f64,
     min_cells: usize,
-) -> Vec<(f64, f64, usize)> {
+) -> PyResult<Vec<(f64, f64, usize)>> {
     points_collections
         .into_par_iter()
         .map(|p| ix_dispersion(p, bbox, r, resample, pval, min_cells))
@@ -59,7 +87,7 @@ pub fn ix_dispersion_3d_parallel(
     resample: usize,
     pval: f64,
     min_cells: usize,
-) -> Vec<(f64, f64, usize)> {
+) -> PyResult<Vec<(f64, f64, usize)>> {
     points_collections
         .into_par_iter()
         .map(|p| ix_dispersion_3d(p, bbox, r, resample, pval, min_cells))
@@ -74,7 +102,7 @@ pub fn morisita_parallel(
     rect_side: Option<(f64, f64)>,
     pval: f64,
     min_cells: usize,
-) -> Vec<(f64, f64, usize)> {
+) -> PyResult<Vec<(f64, f64, usize)>> {
     points_collections
         .into_par_iter()
         .map(|p| morisita_ix(p, bbox, quad, rect_side, pval, min_cells))
@@ -87,7 +115,7 @@ pub fn clark_evans_parallel(
     bbox: BBox,
     pval: f64,
     min_cells: usize,
-) -> Vec<(f64, f64, usize)> {
+) -> PyResult<Vec<(f64, f64, usize)>> {
     points_collections
         .into_par_iter()
         .map(|p| clark_evans_ix(p, bbox, pval, min_cells))
@@ -102,14 +130,17 @@ pub fn ix_dispersion(
     resample: usize,
     pval: f64,
     min_cells: usize,
-) -> (f64, f64, usize) // return (index_value, p_value, pattern)
+) -> PyResult<(f64, f64, usize)> // return (index_value, p_value, pattern)
 {
     let n = points.len();
-    return if n < min_cells {
+    check_points(&points)?;
+    check_bbox(bbox)?;
+    check_resample(resample)?;
+    Ok(if (n < min_cells) | (n < 2) {
         EMPTY_RETURN
     } else {
         let labels: Vec<usize> = (0..n).into_iter().collect();
-        let tree = kdtree_builder(&points, &labels);
+        let tree = kdtree_builder(&points, &labels)?;
         let mut counts = vec![0.0; resample];
         let mut rng = StdRng::seed_from_u64(SEED);
         for i in 0..resample {
@@ -133,7 +164,7 @@ pub fn ix_dispersion(
         } else {
             EMPTY_RETURN
         } // if sample nothing, return 0
-    };
+    })
 }
 
 #[pyfunction]
@@ -144,14 +175,17 @@ pub fn ix_dispersion_3d(
     resample: usize,
     pval: f64,
     min_cells: usize,
-) -> (f64, f64, usize) // return (index_value, p_value, pattern)
+) -> PyResult<(f64, f64, usize)> // return (index_value, p_value, pattern)
 {
     let n = points.len();
-    return if n < min_cells {
+    check_points(&points)?;
+    check_bbox_3d(bbox)?;
+    check_resample(resample)?;
+    Ok(if (n < min_cells) | (n < 2) {
         EMPTY_RETURN
     } else {
         let labels: Vec<usize> = (0..n).into_iter().collect();
-        let tree = kdtree_builder(&points, &labels);
+        let tree = kdtree_builder(&points, &labels)?;
         let mut counts = vec![0.0; resample];
         let mut rng = StdRng::seed_from_u64(SEED);
         for i in 0..resample {
@@ -176,7 +210,7 @@ pub fn ix_dispersion_3d(
         } else {
             EMPTY_RETURN
         } // if sample nothing, return 0
-    };
+    })
 }
 
 #[pyfunction]
@@ -187,12 +221,13 @@ pub fn morisita_ix(
     rect_side: Option<(f64, f64)>,
     pval: f64,
     min_cells: usize,
-) -> (f64, f64, usize) {
+) -> PyResult<(f64, f64, usize)> {
     let n = points.len();
-    return if n < min_cells {
+    check_points(&points)?;
+    Ok(if (n < min_cells) | (n < 2) {
         EMPTY_RETURN
     } else {
-        let counts = QuadStats::new().grid_counts(points, Option::from(bbox), quad, rect_side);
+        let counts = QuadStats::new().grid_counts(points, Option::from(bbox), quad, rect_side)?;
         let quad_count =
             Array1::from_vec(counts.values().into_iter().map(|x| *x as f64).collect_vec());
         let sum_x = quad_count.sum();
@@ -206,7 +241,7 @@ pub fn morisita_ix(
         } else {
             EMPTY_RETURN
         }
-    };
+    })
 }
 
 #[pyfunction]
@@ -215,13 +250,15 @@ pub fn clark_evans_ix(
     bbox: BBox,
     pval: f64,
     min_cells: usize,
-) -> (f64, f64, usize) {
+) -> PyResult<(f64, f64, usize)> {
     let n = points.len();
-    return if n < min_cells {
+    check_points(&points)?;
+    check_bbox(bbox)?;
+    Ok(if (n < min_cells) | (n < 2) {
         EMPTY_RETURN
     } else {
         let labels: Vec<usize> = (0..n).into_iter().collect();
-        let tree = kdtree_builder(&points, &labels);
+        let tree = kdtree_builder(&points, &labels)?;
 
         let area = (bbox.2 - bbox.0) * (bbox.3 - bbox.1);
         let r: Array1<f64> = points
@@ -253,7 +290,7 @@ pub fn clark_evans_ix(
             1
         };
         (big_r, p_value, pattern)
-    };
+    })
 }
 
 fn get_pattern(v: f64, p_value: f64, pval: f64) -> usize {
@@ -6,11 +6,13 @@ use counter::Counter;
 use itertools_num::linspace;
 use ndarray::prelude::*;
 use ordered_float::OrderedFloat;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use rayon::prelude::*;
 
 use crate::custom_type::{Point2D, Point3D};
 use crate::neighbors_search::{points_neighbors_kdtree, points_neighbors_kdtree_3d};
+use crate::utils::{check_collections, check_points, check_same_length};
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(leibovici_parallel, m)?)?;
@@ -25,7 +27,8 @@ pub fn leibovici_parallel(
     points_collections: Vec<Vec<Point2D>>,
     types_collections: Vec<Vec<&str>>,
     d: f64,
-) -> Vec<f64> {
+) -> PyResult<Vec<f64>> {
+    check_collections(&points_collections, &types_collections)?;
     points_collections
         .into_par_iter()
         .zip(types_collections)
@@ -38,7 +41,8 @@ pub fn leibovici_3d_parallel(
     points_collections: Vec<Vec<Point3D>>,
     types_collections: Vec<Vec<&str>>,
     d: f64,
-) -> Vec<f64> {
+) -> PyResult<Vec<f64>> {
+    check_collections(&points_collections, &types_collections)?;
     points_collections
         .into_par_iter()
         .zip(types_collections)
@@ -51,7 +55,8 @@ pub fn altieri_parallel(
     points_collections: Vec<Vec<Point2D>>,
     types_collections: Vec<Vec<&str>>,
     cut: usize,
-) -> Vec<f64> {
+) -> PyResult<Vec<f64>> {
+    check_collections(&points_collections, &types_collections)?;
     points_collections
         .into_par_iter()
         .zip(types_collections)
@@ -64,7 +69,8 @@ pub fn altieri_3d_parallel(
     points_collections: Vec<Vec<Point3D>>,
     types_collections: Vec<Vec<&str>>,
     cut: usize,
-) -> Vec<f64> {
+) -> PyResult<Vec<f64>> {
+    check_collections(&points_collections, &types_collections)?;
     points_collections
         .into_par_iter()
         .zip(types_collections)
@@ -72,14 +78,16 @@ pub fn altieri_3d_parallel(
         .collect()
 }
 
-pub fn leibovici_entropy(points: Vec<Point2D>, types: Vec<&str>, d: f64) -> f64 {
-    let neighbors = points_neighbors_kdtree(points, (0..types.len()).collect(), d, 0);
-    leibovici_base(neighbors, types)
+pub fn leibovici_entropy(points: Vec<Point2D>, types: Vec<&str>, d: f64) -> PyResult<f64> {
+    check_same_length("points", points.len(), "types", types.len())?;
+    let neighbors = points_neighbors_kdtree(points, (0..types.len()).collect(), d, 0)?;
+    Ok(leibovici_base(neighbors, types))
 }
 
-pub fn leibovici_entropy_3d(points: Vec<Point3D>, types: Vec<&str>, d: f64) -> f64 {
-    let neighbors = points_neighbors_kdtree_3d(points, (0..types.len()).collect(), d, 0);
-    leibovici_base(neighbors, types)
+pub fn leibovici_entropy_3d(points: Vec<Point3D>, types: Vec<&str>, d: f64) -> PyResult<f64> {
+    check_same_length("points", points.len(), "types", types.len())?;
+    let neighbors = points_neighbors_kdtree_3d(points, (0..types.len()).collect(), d, 0)?;
+    Ok(leibovici_base(neighbors, types))
 }
 
 fn leibovici_base(neighbors: Vec<Vec<usize>>, types: Vec<&str>) -> f64 {
@@ -96,14 +104,34 @@ fn leibovici_base(neighbors: Vec<Vec<usize>>, types: Vec<&str>) -> f64 {
     v.mapv(|i| i * (1.0 / i).log2()).sum()
 }
 
-pub fn altieri_entropy(points: Vec<Point2D>, types: Vec<&str>, cut: usize) -> f64 {
+pub fn altieri_entropy(points: Vec<Point2D>, types: Vec<&str>, cut: usize) -> PyResult<f64> {
+    check_altieri(&points, &types, cut)?;
+    if points.len() < 2 {
+        return Ok(0.0);
+    }
     let pdist = pdist(points);
-    altieri_base(pdist, types, cut)
+    Ok(altieri_base(pdist, types, cut))
 }
 
-pub fn altieri_entropy_3d(points: Vec<Point3D>, types: Vec<&str>, cut: usize) -> f64 {
+pub fn altieri_entropy_3d(points: Vec<Point3D>, types: Vec<&str>, cut: usize) -> PyResult<f64> {
+    check_altieri(&points, &types, cut)?;
+    if points.len() < 2 {
+        return Ok(0.0);
+    }
     let pdist = pdist(points);
-    altieri_base(pdist, types, cut)
+    Ok(altieri_base(pdist, types, cut))
+}
+
+fn check_altieri<const K: usize>(points: &[[f64; K]], types: &[&str], cut: usize) -> PyResult<()> {
+    check_same_length("points", points.len(), "types", types.len())?;
+    check_points(points)?;
+    if cut < 2 {
+        return Err(PyValueError::new_err(format!(
+            "cut must be at least 2 to make a distance interval, got {}",
+            cut
+        )));
+    }
+    Ok(())
 }
 
 fn altieri_base(pdist: Vec<OrderedFloat<f64>>, types: Vec<&str>, cut: usize) -> f64 {
@@ -249,7 +277,7 @@ mod tests {
     fn test_leibovici() {
         let points = vec![[1.0, 0.0], [3.0, 0.0], [1.0, 6.0], [3.0, 11.0]];
         let types = vec!["1", "2", "3", "4"];
-        let e = leibovici_entropy(points, types, 10.0);
+        let e = leibovici_entropy(points, types, 10.0).unwrap();
         println!("leibovici entropy is {:?}", e);
     }
 
@@ -257,7 +285,7 @@ mod tests {
     fn test_altieri() {
         let points = vec![[1.0, 0.0], [3.0, 0.0], [1.0, 6.0], [3.0, 11.0]];
         let types = vec!["1", "2", "3", "4"];
-        let e = altieri_entropy(points, types, 3);
+        let e = altieri_entropy(points, types, 3).unwrap();
         println!("altieri entropy is {:?}", e);
     }
 }
@@ -3,12 +3,18 @@ use geo::algorithm::bounding_rect::BoundingRect;
 use geo::algorithm::concave_hull::ConcaveHull;
 use geo::algorithm::convex_hull::ConvexHull;
 use geo::algorithm::coords_iter::CoordsIter;
-use geo::{LineString, Polygon};
+use geo::{LineString, MultiPolygon, Polygon};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use rayon::prelude::*;
-use rstar::AABB;
+use rstar::primitives::{GeomWithData, Line as SegmentLine};
+use rstar::{Envelope, PointDistance, RTree, RTreeObject, AABB};
 
-use crate::custom_type::{BBox, BBox3D, Point2D, Point3D};
+use crate::convex_hull_3d::{convex_hull_3d, hull_volume_area};
+use crate::custom_type::{BBox, BBox3D, MultiPolygonRings, Point2D, Point3D, PolygonRings};
+use crate::utils::{check_collections, check_not_empty, check_points, check_polygon};
+
+type RegionSegment = GeomWithData<SegmentLine<Point2D>, usize>;
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(points_bbox, m)?)?;
@@ -21,36 +27,59 @@ pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(convex, m)?)?;
     m.add_function(wrap_pyfunction!(multipolygons_concave, m)?)?;
     m.add_function(wrap_pyfunction!(multipolygons_convex, m)?)?;
+    m.add_function(wrap_pyfunction!(polygon_morphology, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_morphology, m)?)?;
+    m.add_function(wrap_pyfunction!(polygon_centroid, m)?)?;
+    m.add_function(wrap_pyfunction!(multipolygons_centroid, m)?)?;
+    m.add_function(wrap_pyfunction!(polygon_interior_point, m)?)?;
+    m.add_function(wrap_pyfunction!(multipolygons_interior_point, m)?)?;
+    m.add_function(wrap_pyfunction!(points_in_regions, m)?)?;
+    m.add_function(wrap_pyfunction!(points_in_regions_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_signed_distance, m)?)?;
+    m.add_function(wrap_pyfunction!(points_signed_distance_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(distance_bands, m)?)?;
+    m.add_function(wrap_pyfunction!(rings_area, m)?)?;
+    m.add_function(wrap_pyfunction!(rings_bbox, m)?)?;
+    m.add_function(wrap_pyfunction!(rings_centroid, m)?)?;
+    m.add_function(wrap_pyfunction!(rings_interior_point, m)?)?;
+    m.add_function(wrap_pyfunction!(rings_convex, m)?)?;
+    m.add_function(wrap_pyfunction!(rings_concave, m)?)?;
+    m.add_function(wrap_pyfunction!(points3d_convex_hull, m)?)?;
+    m.add_function(wrap_pyfunction!(multipoints3d_convex_hull, m)?)?;
+    m.add_function(wrap_pyfunction!(points3d_hull_volume_area, m)?)?;
+    m.add_function(wrap_pyfunction!(multipoints3d_hull_volume_area, m)?)?;
     Ok(())
 }
 
 #[pyfunction]
 #[pyo3(name = "_points_bbox")]
-pub fn points_bbox(p: Vec<Point2D>) -> BBox {
+pub fn points_bbox(p: Vec<Point2D>) -> PyResult<BBox> {
     // minx, miny, maxx, maxy
+    check_not_empty("points", p.len())?;
     let line_string: LineString<f64> = p.into();
     let bounding_rect = line_string.bounding_rect().unwrap();
-    (
+    Ok((
         bounding_rect.min().x,
         bounding_rect.min().y,
         bounding_rect.max().x,
         bounding_rect.max().y,
-    )
+    ))
 }
 
 #[pyfunction]
 #[pyo3(name = "_points3d_bbox")]
-pub fn points3d_bbox(p: Vec<Point3D>) -> BBox3D {
+pub fn points3d_bbox(p: Vec<Point3D>) -> PyResult<BBox3D> {
     // minx, miny, minz, maxx, maxy, maxz
+    check_not_empty("points", p.len())?;
     let bbox: AABB<Point3D> = AABB::from_points(p.iter());
     let lower = bbox.lower();
     let upper = bbox.upper();
-    (lower[0], lower[1], lower[2], upper[0], upper[1], upper[2])
+    Ok((lower[0], lower[1], lower[2], upper[0], upper[1], upper[2]))
 }
 
 #[pyfunction]
 #[pyo3(name = "_multipoints_bbox")]
-pub fn multipoints_bbox(points_collections: Vec<Vec<Point2D>>) -> Vec<BBox> {
+pub fn multipoints_bbox(points_collections: Vec<Vec<Point2D>>) -> PyResult<Vec<BBox>> {
     points_collections
         .into_iter()
         .map(|p| points_bbox(p))
@@ -59,7 +88,7 @@ pub fn multipoints_bbox(points_collections: Vec<Vec<Point2D>>) -> Vec<BBox> {
 
 #[pyfunction]
 #[pyo3(name = "_multipoints3d_bbox")]
-pub fn multipoints3d_bbox(points_collections: Vec<Vec<Point3D>>) -> Vec<BBox3D> {
+pub fn multipoints3d_bbox(points_collections: Vec<Vec<Point3D>>) -> PyResult<Vec<BBox3D>> {
     points_collections
         .into_iter()
         .map(|p| points3d_bbox(p))
@@ -83,29 +112,736 @@ pub fn multipolygons_area(points_collections: Vec<Vec<Point2D>>) -> Vec<f64> {
 }
 
 #[pyfunction]
-pub fn concave(p: Vec<Point2D>, concavity: f64) -> Vec<Point2D> {
+pub fn concave(p: Vec<Point2D>, concavity: f64) -> PyResult<Vec<Point2D>> {
+    check_not_empty("points", p.len())?;
+    check_points(&p)?;
     let line_string: LineString<f64> = p.into();
     let res = line_string.concave_hull(concavity);
-    res.exterior_coords_iter()
+    Ok(res
+        .exterior_coords_iter()
         .map(|coord| [coord.x, coord.y])
-        .collect()
+        .collect())
 }
 
 #[pyfunction]
-pub fn multipolygons_concave(polygons: Vec<Vec<Point2D>>, concavity: f64) -> Vec<Vec<Point2D>> {
+pub fn multipolygons_concave(
+    polygons: Vec<Vec<Point2D>>,
+    concavity: f64,
+) -> PyResult<Vec<Vec<Point2D>>> {
     polygons.into_par_iter().map(|p| concave(p, concavity)).collect()
 }
 
 #[pyfunction]
-pub fn convex(p: Vec<Point2D>) -> Vec<Point2D> {
+pub fn convex(p: Vec<Point2D>) -> PyResult<Vec<Point2D>> {
+    check_not_empty("points", p.len())?;
+    check_points(&p)?;
     let line_string: LineString<f64> = p.into();
     let res = line_string.convex_hull();
-    res.exterior_coords_iter()
+    Ok(res
+        .exterior_coords_iter()
         .map(|coord| [coord.x, coord.y])
-        .collect()
+        .collect())
 }
 
 #[pyfunction]
-pub fn multipolygons_convex(polygons: Vec<Vec<Point2D>>) -> Vec<Vec<Point2D>> {
+pub fn multipolygons_convex(polygons: Vec<Vec<Point2D>>) -> PyResult<Vec<Vec<Point2D>>> {
     polygons.into_par_iter().map(|p| convex(p)).collect()
-}
\ No newline at end of file
+}
+
+// Shape descriptors of a polygon, in the order of
+//   area, perimeter, centroid_x, centroid_y, equivalent_diameter,
+//   major_axis_length, minor_axis_length, orientation, eccentricity,
+//   solidity, circularity, extent
+// The axis and orientation come from the second moments of the filled polygon,
+// the axis length is the one of the ellipse with the same second moments,
+// the orientation is the angle in radians between the x-axis and the major axis.
+// A degenerate polygon with zero area gets NaN for the features divided by area.
+#[pyfunction]
+pub fn polygon_morphology(p: Vec<Point2D>) -> PyResult<Vec<f64>> {
+    check_polygon(&p)?;
+    let (area, cx, cy, mu20, mu02, mu11) = polygon_moments(&p);
+
+    let n = p.len();
+    let perimeter: f64 = (0..n)
+        .map(|i| {
+            let (a, b) = (p[i], p[(i + 1) % n]);
+            ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt()
+        })
+        .sum();
+    let equivalent_diameter = (4.0 * area / std::f64::consts::PI).sqrt();
+
+    // The eigenvalues of the covariance matrix
+    let half_trace = (mu20 + mu02) / 2.0;
+    let delta = (((mu20 - mu02) / 2.0).powi(2) + mu11.powi(2)).sqrt();
+    let l1 = half_trace + delta;
+    let l2 = (half_trace - delta).max(0.0);
+    let major_axis_length = 4.0 * l1.sqrt();
+    let minor_axis_length = 4.0 * l2.sqrt();
+    let orientation = 0.5 * (2.0 * mu11).atan2(mu20 - mu02);
+    let eccentricity = if l1 > 0.0 {
+        (1.0 - l2 / l1).sqrt()
+    } else {
+        f64::NAN
+    };
+
+    let line_string: LineString<f64> = p.into();
+    let convex_area = line_string.convex_hull().unsigned_area();
+    let rect = line_string.bounding_rect().unwrap();
+    let solidity = area / convex_area;
+    let circularity = 4.0 * std::f64::consts::PI * area / perimeter.powi(2);
+    let extent = area / (rect.width() * rect.height());
+
+    Ok(vec![
+        area,
+        perimeter,
+        cx,
+        cy,
+        equivalent_diameter,
+        major_axis_length,
+        minor_axis_length,
+        orientation,
+        eccentricity,
+        solidity,
+        circularity,
+        extent,
+    ])
+}
+
+#[pyfunction]
+pub fn polygons_morphology(polygons: Vec<Vec<Point2D>>) -> PyResult<Vec<Vec<f64>>> {
+    polygons.into_par_iter().map(polygon_morphology).collect()
+}
+
+// The area, centroid and central second moments (normalized by area) of a polygon
+// by the Green's theorem, the coordinates are shifted to the first vertex to reduce
+// the floating error. The moments don't depend on the orientation of the ring.
+pub fn polygon_moments(p: &[Point2D]) -> (f64, f64, f64, f64, f64, f64) {
+    let n = p.len();
+    let [ox, oy] = p[0];
+    let (mut a, mut sx, mut sy, mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    for i in 0..n {
+        let (x0, y0) = (p[i][0] - ox, p[i][1] - oy);
+        let (x1, y1) = (p[(i + 1) % n][0] - ox, p[(i + 1) % n][1] - oy);
+        let cross = x0 * y1 - x1 * y0;
+        a += cross;
+        sx += (x0 + x1) * cross;
+        sy += (y0 + y1) * cross;
+        sxx += (x0 * x0 + x0 * x1 + x1 * x1) * cross;
+        syy += (y0 * y0 + y0 * y1 + y1 * y1) * cross;
+        sxy += (x0 * y1 + 2.0 * x0 * y0 + 2.0 * x1 * y1 + x1 * y0) * cross;
+    }
+    let a = a / 2.0;
+    let cx = sx / (6.0 * a);
+    let cy = sy / (6.0 * a);
+    let mu20 = sxx / (12.0 * a) - cx * cx;
+    let mu02 = syy / (12.0 * a) - cy * cy;
+    let mu11 = sxy / (24.0 * a) - cx * cy;
+    (a.abs(), cx + ox, cy + oy, mu20, mu02, mu11)
+}
+
+// The area-weighted centroid of a polygon, a degenerate polygon with (near) zero area,
+// like a line or a self-intersecting polygon whose parts cancel out, falls back to
+// the length-weighted centroid of the ring, and then the mean of the vertices
+#[pyfunction]
+pub fn polygon_centroid(p: Vec<Point2D>) -> PyResult<Point2D> {
+    check_not_empty("polygon", p.len())?;
+    check_points(&p)?;
+    Ok(multipolygon_centroid(&[vec![p]]))
+}
+
+#[pyfunction]
+pub fn multipolygons_centroid(polygons: Vec<Vec<Point2D>>) -> PyResult<Vec<Point2D>> {
+    polygons.into_par_iter().map(polygon_centroid).collect()
+}
+
+// A point guaranteed to be inside the polygon, useful when the centroid of
+// a concave shape falls outside. The interior follows the even-odd rule so that
+// self-intersecting polygons are handled, a polygon without interior returns a vertex.
+#[pyfunction]
+pub fn polygon_interior_point(p: Vec<Point2D>) -> PyResult<Point2D> {
+    check_not_empty("polygon", p.len())?;
+    check_points(&p)?;
+    Ok(multipolygon_interior_point(&[vec![p]]))
+}
+
+#[pyfunction]
+pub fn multipolygons_interior_point(polygons: Vec<Vec<Point2D>>) -> PyResult<Vec<Point2D>> {
+    polygons.into_par_iter().map(polygon_interior_point).collect()
+}
+
+// The centroid of the parts weighted by their area, the holes are subtracted,
+// the first ring of each part is the exterior
+pub fn multipolygon_centroid(parts: &[PolygonRings]) -> Point2D {
+    let (mut area, mut sx, mut sy) = (0.0, 0.0, 0.0);
+    for rings in parts {
+        for (ix, ring) in rings.iter().enumerate().filter(|(_, r)| r.len() >= 3) {
+            let (a, cx, cy, _, _, _) = polygon_moments(ring);
+            let a = if ix == 0 { a } else { -a };
+            area += a;
+            sx += a * cx;
+            sy += a * cy;
+        }
+    }
+    let rings: Vec<&Vec<Point2D>> = parts.iter().flatten().collect();
+    let (minx, miny, maxx, maxy) = rings.iter().flat_map(|r| r.iter()).fold(
+        (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        |b, c| (b.0.min(c[0]), b.1.min(c[1]), b.2.max(c[0]), b.3.max(c[1])),
+    );
+    let scale = (maxx - minx).max(maxy - miny);
+    if area > f64::EPSILON * scale * scale {
+        return [sx / area, sy / area];
+    }
+    let (mut length, mut sx, mut sy) = (0.0, 0.0, 0.0);
+    for p in &rings {
+        let n = p.len();
+        for i in 0..n {
+            let (a, b) = (p[i], p[(i + 1) % n]);
+            let l = ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt();
+            length += l;
+            sx += l * (a[0] + b[0]) / 2.0;
+            sy += l * (a[1] + b[1]) / 2.0;
+        }
+    }
+    if length > 0.0 {
+        [sx / length, sy / length]
+    } else {
+        rings[0][0]
+    }
+}
+
+// Cast a horizontal line through the middle of each part, the midpoint of
+// the longest inside span is the interior point. All the rings are crossed by
+// the line, so the spans inside the holes are outside by the even-odd rule.
+pub fn multipolygon_interior_point(parts: &[PolygonRings]) -> Point2D {
+    let rings: Vec<&Vec<Point2D>> = parts.iter().flatten().collect();
+    let widest = parts
+        .iter()
+        .filter_map(|part| {
+            let (miny, maxy) = part
+                .iter()
+                .flatten()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), c| {
+                    (lo.min(c[1]), hi.max(c[1]))
+                });
+            let y = scanline_y(&rings, (miny + maxy) / 2.0);
+            widest_span(&rings, y).map(|(x0, x1)| (x1 - x0, [(x0 + x1) / 2.0, y]))
+        })
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    match widest {
+        Some((_, p)) => p,
+        // No interior, return the vertex closest to the centroid
+        None => {
+            let c = multipolygon_centroid(parts);
+            *rings
+                .iter()
+                .flat_map(|r| r.iter())
+                .min_by(|a, b| {
+                    let da = (a[0] - c[0]).powi(2) + (a[1] - c[1]).powi(2);
+                    let db = (b[0] - c[0]).powi(2) + (b[1] - c[1]).powi(2);
+                    da.partial_cmp(&db).unwrap()
+                })
+                .unwrap()
+        }
+    }
+}
+
+// Avoid passing through a vertex, move to the middle of the vertex and the next closest one
+fn scanline_y(rings: &[&Vec<Point2D>], y: f64) -> f64 {
+    let ys = rings.iter().flat_map(|r| r.iter()).map(|c| c[1]);
+    if !ys.clone().any(|cy| cy == y) {
+        return y;
+    }
+    ys.filter(|cy| *cy != y)
+        .min_by(|a, b| (a - y).abs().partial_cmp(&(b - y).abs()).unwrap())
+        .map_or(y, |next| (y + next) / 2.0)
+}
+
+// The longest span of the line at `y` that is inside the rings
+fn widest_span(rings: &[&Vec<Point2D>], y: f64) -> Option<(f64, f64)> {
+    let mut xs: Vec<f64> = rings
+        .iter()
+        .flat_map(|p| {
+            let n = p.len();
+            (0..n).filter_map(move |i| {
+                let (a, b) = (p[i], p[(i + 1) % n]);
+                // Half open to count the crossing at a vertex once
+                if (a[1] > y) != (b[1] > y) {
+                    Some(a[0] + (y - a[1]) / (b[1] - a[1]) * (b[0] - a[0]))
+                } else {
+                    None
+                }
+            })
+        })
+        .collect();
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    xs.chunks_exact(2)
+        .map(|span| (span[0], span[1]))
+        .filter(|(x0, x1)| x1 > x0)
+        .max_by(|a, b| (a.1 - a.0).partial_cmp(&(b.1 - b.0)).unwrap())
+}
+
+// Find the regions that contain each point, the regions are polygons with holes.
+// A point on the border belongs to the region, so a point may have several regions
+// where regions share a border, or no region at all.
+#[pyfunction]
+pub fn points_in_regions(
+    points: Vec<Point2D>,
+    regions: Vec<PolygonRings>,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_points(&points)?;
+    let regions: Vec<Polygon<f64>> = regions
+        .into_iter()
+        .map(rings_polygon)
+        .collect::<PyResult<_>>()?;
+    Ok(regions_containing(&points, &regions))
+}
+
+#[pyfunction]
+pub fn points_in_regions_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    regions_collections: Vec<Vec<PolygonRings>>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &regions_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(regions_collections)
+        .map(|(ps, rs)| points_in_regions(ps, rs))
+        .collect()
+}
+
+// Build a polygon from the exterior ring and the holes
+pub fn rings_polygon(rings: PolygonRings) -> PyResult<Polygon<f64>> {
+    check_not_empty("polygon", rings.len())?;
+    for ring in &rings {
+        check_polygon(ring)?;
+    }
+    let mut rings = rings.into_iter().map(LineString::from);
+    let exterior = rings.next().unwrap();
+    Ok(Polygon::new(exterior, rings.collect()))
+}
+
+pub fn rings_multipolygon(parts: MultiPolygonRings) -> PyResult<MultiPolygon<f64>> {
+    check_not_empty("multipolygon", parts.len())?;
+    Ok(MultiPolygon(
+        parts
+            .into_iter()
+            .map(rings_polygon)
+            .collect::<PyResult<_>>()?,
+    ))
+}
+
+pub fn polygon_rings(polygon: &Polygon<f64>) -> PolygonRings {
+    std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .map(|ring| ring.coords().map(|c| [c.x, c.y]).collect())
+        .collect()
+}
+
+// The border segments of the regions are indexed in a R-tree, a ray is cast from
+// the point to the nearer side of the regions, a point is inside a region if the ray
+// crosses its border an odd number of times, so the holes are excluded
+pub fn regions_containing(points: &[Point2D], regions: &[Polygon<f64>]) -> Vec<Vec<usize>> {
+    let tree = region_segments(regions);
+    points
+        .par_iter()
+        .map(|p| point_regions(&tree, p))
+        .collect()
+}
+
+// The border segments of the regions, the data is the index of the region
+pub fn region_segments(regions: &[Polygon<f64>]) -> RTree<RegionSegment> {
+    let segments: Vec<RegionSegment> = regions
+        .iter()
+        .enumerate()
+        .flat_map(|(i, r)| {
+            std::iter::once(r.exterior())
+                .chain(r.interiors())
+                .flat_map(|ring| ring.lines())
+                .map(move |l| GeomWithData::new(SegmentLine::new(l.start.into(), l.end.into()), i))
+        })
+        .collect();
+    RTree::bulk_load(segments)
+}
+
+// The sorted indices of the regions containing the point, the border is inside
+pub fn point_regions(tree: &RTree<RegionSegment>, p: &Point2D) -> Vec<usize> {
+    let bounds = tree.root().envelope();
+    if !bounds.contains_point(p) {
+        return vec![];
+    }
+    let (lower, upper) = (bounds.lower(), bounds.upper());
+    let to_right = upper[0] - p[0] <= p[0] - lower[0];
+    let ray = if to_right {
+        AABB::from_corners(*p, [upper[0], p[1]])
+    } else {
+        AABB::from_corners([lower[0], p[1]], *p)
+    };
+    let mut crossed = vec![];
+    let mut on_border = vec![];
+    for s in tree.locate_in_envelope_intersecting(&ray) {
+        let (a, b) = (s.geom().from, s.geom().to);
+        let side = (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0]);
+        if side == 0.0 {
+            if s.envelope().contains_point(p) {
+                on_border.push(s.data);
+            }
+            continue;
+        }
+        // Half open to count the crossing at a vertex once,
+        // the segment is at the right of the point if the point is at the left of
+        // an upward segment, or at the right of a downward segment
+        if ((a[1] > p[1]) != (b[1] > p[1])) && (((side > 0.0) == (b[1] > a[1])) == to_right) {
+            crossed.push(s.data);
+        }
+    }
+    crossed.sort_unstable();
+    let mut within = on_border;
+    let mut start = 0;
+    for end in 1..=crossed.len() {
+        if (end == crossed.len()) || (crossed[end] != crossed[start]) {
+            if (end - start) % 2 == 1 {
+                within.push(crossed[start]);
+            }
+            start = end;
+        }
+    }
+    within.sort_unstable();
+    within.dedup();
+    within
+}
+
+// The distance from each point to the nearest boundary of the regions,
+// negative inside a region and positive outside, the boundary includes the holes.
+// The border segments are indexed by a R-tree for the nearest search.
+#[pyfunction]
+pub fn points_signed_distance(
+    points: Vec<Point2D>,
+    regions: Vec<PolygonRings>,
+) -> PyResult<Vec<f64>> {
+    check_points(&points)?;
+    check_not_empty("regions", regions.len())?;
+    let regions: Vec<Polygon<f64>> = regions
+        .into_iter()
+        .map(rings_polygon)
+        .collect::<PyResult<_>>()?;
+    Ok(signed_distance(&points, &regions))
+}
+
+#[pyfunction]
+pub fn points_signed_distance_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    regions_collections: Vec<Vec<PolygonRings>>,
+) -> PyResult<Vec<Vec<f64>>> {
+    check_collections(&points_collections, &regions_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(regions_collections)
+        .map(|(ps, rs)| points_signed_distance(ps, rs))
+        .collect()
+}
+
+// Bin the distances by the increasing breaks, the band of a distance d is
+// the number of breaks <= d, so there are len(breaks) + 1 bands
+#[pyfunction]
+pub fn distance_bands(distances: Vec<f64>, breaks: Vec<f64>) -> PyResult<Vec<usize>> {
+    if breaks.iter().any(|b| !b.is_finite()) || breaks.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(PyValueError::new_err(format!(
+            "The breaks must be finite and strictly increasing, got {:?}",
+            breaks
+        )));
+    }
+    Ok(distances
+        .par_iter()
+        .map(|d| breaks.partition_point(|b| b <= d))
+        .collect())
+}
+
+pub fn signed_distance(points: &[Point2D], regions: &[Polygon<f64>]) -> Vec<f64> {
+    let tree = region_segments(regions);
+    points
+        .par_iter()
+        .map(|p| {
+            let d = tree.nearest_neighbor(p).unwrap().distance_2(p).sqrt();
+            if (d == 0.0) || point_regions(&tree, p).is_empty() {
+                d
+            } else {
+                -d
+            }
+        })
+        .collect()
+}
+
+// The functions below work on multipolygons with holes, a polygon with holes
+// is a multipolygon of one part
+
+// The area of the exterior minus the holes
+#[pyfunction]
+#[pyo3(name = "_rings_area")]
+pub fn rings_area(multipolygons: Vec<MultiPolygonRings>) -> PyResult<Vec<f64>> {
+    multipolygons
+        .into_par_iter()
+        .map(|mp| Ok(rings_multipolygon(mp)?.unsigned_area()))
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(name = "_rings_bbox")]
+pub fn rings_bbox(multipolygons: Vec<MultiPolygonRings>) -> PyResult<Vec<BBox>> {
+    multipolygons
+        .into_par_iter()
+        .map(|mp| {
+            let rect = rings_multipolygon(mp)?.bounding_rect().unwrap();
+            Ok((rect.min().x, rect.min().y, rect.max().x, rect.max().y))
+        })
+        .collect()
+}
+
+// The parts without area, like lines, are accepted as in `polygon_centroid`
+#[pyfunction]
+#[pyo3(name = "_rings_centroid")]
+pub fn rings_centroid(multipolygons: Vec<MultiPolygonRings>) -> PyResult<Vec<Point2D>> {
+    multipolygons
+        .into_par_iter()
+        .map(|mp| {
+            check_rings(&mp)?;
+            Ok(multipolygon_centroid(&mp))
+        })
+        .collect()
+}
+
+#[pyfunction]
+#[pyo3(name = "_rings_interior_point")]
+pub fn rings_interior_point(multipolygons: Vec<MultiPolygonRings>) -> PyResult<Vec<Point2D>> {
+    multipolygons
+        .into_par_iter()
+        .map(|mp| {
+            check_rings(&mp)?;
+            Ok(multipolygon_interior_point(&mp))
+        })
+        .collect()
+}
+
+fn check_rings(parts: &MultiPolygonRings) -> PyResult<()> {
+    check_not_empty("multipolygon", parts.len())?;
+    for rings in parts {
+        check_not_empty("polygon", rings.len())?;
+        for ring in rings {
+            check_not_empty("ring", ring.len())?;
+            check_points(ring)?;
+        }
+    }
+    Ok(())
+}
+
+// The hull covers all the parts, the holes don't change the hull
+#[pyfunction]
+pub fn rings_convex(multipolygons: Vec<MultiPolygonRings>) -> PyResult<Vec<Vec<Point2D>>> {
+    multipolygons
+        .into_par_iter()
+        .map(|mp| {
+            let hull = rings_multipolygon(mp)?.convex_hull();
+            Ok(polygon_rings(&hull).swap_remove(0))
+        })
+        .collect()
+}
+
+#[pyfunction]
+pub fn rings_concave(
+    multipolygons: Vec<MultiPolygonRings>,
+    concavity: f64,
+) -> PyResult<Vec<Vec<Point2D>>> {
+    multipolygons
+        .into_par_iter()
+        .map(|mp| {
+            let exterior: Vec<Point2D> = rings_multipolygon(mp)?
+                .iter()
+                .flat_map(|p| p.exterior().coords().map(|c| [c.x, c.y]))
+                .collect();
+            concave(exterior, concavity)
+        })
+        .collect()
+}
+
+// The triangles of the 3D convex hull as the index of points, the normal of
+// each triangle (right-hand rule) points inward. Empty if the points are less than 4 or coplanar.
+#[pyfunction]
+pub fn points3d_convex_hull(p: Vec<Point3D>) -> PyResult<Vec<[usize; 3]>> {
+    check_points(&p)?;
+    Ok(convex_hull_3d(&p).unwrap_or_default())
+}
+
+#[pyfunction]
+pub fn multipoints3d_convex_hull(
+    points_collections: Vec<Vec<Point3D>>,
+) -> PyResult<Vec<Vec<[usize; 3]>>> {
+    points_collections
+        .into_par_iter()
+        .map(points3d_convex_hull)
+        .collect()
+}
+
+// The (volume, surface area) of the 3D convex hull, 0 for points without a hull
+#[pyfunction]
+pub fn points3d_hull_volume_area(p: Vec<Point3D>) -> PyResult<(f64, f64)> {
+    check_points(&p)?;
+    Ok(match convex_hull_3d(&p) {
+        Some(faces) => hull_volume_area(&p, &faces),
+        None => (0.0, 0.0),
+    })
+}
+
+#[pyfunction]
+pub fn multipoints3d_hull_volume_area(
+    points_collections: Vec<Vec<Point3D>>,
+) -> PyResult<Vec<(f64, f64)>> {
+    points_collections
+        .into_par_iter()
+        .map(points3d_hull_volume_area)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::algorithm::intersects::Intersects;
+    use geo::{Point, Polygon};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::custom_type::Point2D;
+    use crate::geo::{
+        distance_bands, multipolygon_centroid, multipolygon_interior_point, points_in_regions,
+        points_signed_distance, polygon_morphology, regions_containing, rings_area, rings_bbox,
+        rings_convex,
+    };
+
+    #[test]
+    fn test_polygon_morphology() {
+        // A 4 x 2 rectangle rotated by 90 degree
+        let rect = vec![[1.0, 1.0], [3.0, 1.0], [3.0, 5.0], [1.0, 5.0]];
+        let m = polygon_morphology(rect).unwrap();
+        assert!((m[0] - 8.0).abs() < 1e-9);
+        assert!((m[1] - 12.0).abs() < 1e-9);
+        assert!((m[2] - 2.0).abs() < 1e-9 && (m[3] - 3.0).abs() < 1e-9);
+        // The variance along the side of length L is L^2 / 12
+        assert!((m[5] - 4.0 * (16.0_f64 / 12.0).sqrt()).abs() < 1e-9);
+        assert!((m[6] - 4.0 * (4.0_f64 / 12.0).sqrt()).abs() < 1e-9);
+        assert!((m[7].abs() - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        assert!((m[9] - 1.0).abs() < 1e-9);
+        assert!((m[11] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_centroid_interior_point() {
+        // A U shape whose centroid is outside
+        let u = vec![
+            [0.0, 0.0],
+            [3.0, 0.0],
+            [3.0, 3.0],
+            [2.0, 3.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 3.0],
+            [0.0, 3.0],
+        ];
+        let c = multipolygon_centroid(&[vec![u.to_owned()]]);
+        assert!((c[0] - 1.5).abs() < 1e-9);
+        let ip = multipolygon_interior_point(&[vec![u.to_owned()]]);
+        assert!((ip[0] < 1.0) | (ip[0] > 2.0) | (ip[1] < 1.0));
+
+        // A line falls back to the middle of the line
+        let line = vec![[0.0, 0.0], [2.0, 2.0], [4.0, 4.0]];
+        assert_eq!(multipolygon_centroid(&[vec![line.to_owned()]]), [2.0, 2.0]);
+        assert_eq!(multipolygon_interior_point(&[vec![line]]), [2.0, 2.0]);
+
+        // A square with a hole in the middle, the scanline through the middle
+        // crosses the hole, the interior point is beside the hole
+        let square = vec![[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]];
+        let hole = vec![[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]];
+        let ip = multipolygon_interior_point(&[vec![square.to_owned(), hole.to_owned()]]);
+        assert!((ip[0] < 1.0) | (ip[0] > 3.0));
+        // The hole on the left side moves the centroid to the right,
+        // a second part of area 4 at the right adds more weight
+        let hole = vec![[0.5, 1.0], [1.5, 1.0], [1.5, 3.0], [0.5, 3.0]];
+        let c = multipolygon_centroid(&[vec![square.to_owned(), hole]]);
+        assert!((c[0] - (16.0 * 2.0 - 2.0 * 1.0) / 14.0).abs() < 1e-9);
+        let parts = vec![vec![square], vec![u.iter().map(|p| [p[0] + 10.0, p[1]]).collect()]];
+        let c = multipolygon_centroid(&parts);
+        assert!((c[0] - (16.0 * 2.0 + 7.0 * 11.5) / 23.0).abs() < 1e-9);
+        // The part with the widest span is used
+        let ip = multipolygon_interior_point(&parts);
+        assert!(ip[0] < 4.0);
+    }
+
+    #[test]
+    fn test_points_in_regions() {
+        // A square with a hole, and a square next to it
+        let donut = vec![
+            vec![[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+            vec![[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]],
+        ];
+        let square = vec![vec![[4.0, 0.0], [6.0, 0.0], [6.0, 2.0], [4.0, 2.0]]];
+        let points = vec![[0.5, 0.5], [2.0, 2.0], [4.0, 1.0], [5.0, 1.0], [9.0, 9.0]];
+        let regions = points_in_regions(points, vec![donut, square]).unwrap();
+        assert_eq!(regions, vec![vec![0], vec![], vec![0, 1], vec![1], vec![]]);
+
+        // Random star polygons with a hole, compared with the point in polygon test of geo
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let star = |rng: &mut ChaCha8Rng, c: Point2D, r: f64| -> Vec<Point2D> {
+            (0..20)
+                .map(|i| {
+                    let angle = i as f64 / 20.0 * std::f64::consts::TAU;
+                    let r = r * rng.gen_range(0.5..1.0);
+                    [c[0] + r * angle.cos(), c[1] + r * angle.sin()]
+                })
+                .collect()
+        };
+        let regions: Vec<Polygon<f64>> = (0..30)
+            .map(|_| {
+                let c = [rng.gen_range(0.0..10.0), rng.gen_range(0.0..10.0)];
+                let exterior = star(&mut rng, c, 2.0);
+                let hole = star(&mut rng, c, 0.8);
+                Polygon::new(exterior.into(), vec![hole.into()])
+            })
+            .collect();
+        let points: Vec<Point2D> = (0..2000)
+            .map(|_| [rng.gen_range(-1.0..11.0), rng.gen_range(-1.0..11.0)])
+            .collect();
+        for (p, within) in points.iter().zip(regions_containing(&points, &regions)) {
+            let expected: Vec<usize> = (0..regions.len())
+                .filter(|i| regions[*i].intersects(&Point::from(*p)))
+                .collect();
+            assert_eq!(within, expected);
+        }
+    }
+
+    #[test]
+    fn test_signed_distance() {
+        let donut = vec![
+            vec![[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+            vec![[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]],
+        ];
+        let points = vec![[0.5, 2.0], [2.0, 2.0], [6.0, 2.0], [4.0, 2.0]];
+        let d = points_signed_distance(points, vec![donut]).unwrap();
+        assert_eq!(d, vec![-0.5, 1.0, 2.0, 0.0]);
+        let bands = distance_bands(d, vec![-1.0, 0.0, 1.0]).unwrap();
+        assert_eq!(bands, vec![1, 3, 3, 2]);
+    }
+
+    #[test]
+    fn test_rings() {
+        let donut = vec![
+            vec![[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+            vec![[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]],
+        ];
+        let square = vec![vec![[5.0, 0.0], [6.0, 0.0], [6.0, 1.0], [5.0, 1.0]]];
+        let shapes = vec![vec![donut.clone()], vec![donut, square]];
+        assert_eq!(rings_area(shapes.clone()).unwrap(), vec![12.0, 13.0]);
+        assert_eq!(
+            rings_bbox(shapes.clone()).unwrap(),
+            vec![(0.0, 0.0, 4.0, 4.0), (0.0, 0.0, 6.0, 4.0)]
+        );
+        let hull = rings_convex(shapes).unwrap();
+        // The hull is closed
+        assert_eq!(hull[0].len(), 5);
+    }
+}
@@ -23,17 +23,17 @@ pub fn hotspot(
     rect_side: Option<(f64, f64)>,
     pval: f64,
     min_cells: usize,
-) -> Vec<bool> {
+) -> PyResult<Vec<bool>> {
     let n = points.len();
     if n == 0 {
-        return vec![];
+        return Ok(vec![]);
     };
     let mut q = QuadStats::new();
-    let counts = q.grid_counts(points, Option::from(bbox), quad, rect_side);
+    let counts = q.grid_counts(points, Option::from(bbox), quad, rect_side)?;
     let nx = q.nx;
     let ny = q.ny;
     let quad_n = (nx * ny) as f64;
-    return if (n < min_cells) | (quad_n < 9.0) {
+    Ok(if (n < min_cells) | (quad_n < 9.0) {
         vec![false; n]
     } else {
         let quad_count = Array::from_shape_vec(
@@ -49,7 +49,7 @@ pub fn hotspot(
         }
 
         let labels: Vec<usize> = (0..idx_points.len()).into_iter().collect();
-        let tree = kdtree_builder(&idx_points, &labels);
+        let tree = kdtree_builder(&idx_points, &labels)?;
 
         let mean_c = quad_count.mean().unwrap();
         let sum_c = quad_count.mapv(|i| i.powi(2)).sum();
@@ -99,5 +99,5 @@ pub fn hotspot(
                 .map(|id| if hot_rect[*id] { true } else { false })
                 .collect()
         }
-    };
+    })
 }
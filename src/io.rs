@@ -2,16 +2,22 @@ use std::convert::TryFrom;
 use std::str::FromStr;
 
 use geo::{LineString, Point};
+use pyo3::exceptions::{PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use wkt::{ToWkt, Wkt};
 
-use crate::custom_type::Point2D;
+use crate::custom_type::{MultiPolygonRings, Point2D, PolygonRings};
+use crate::geo::{polygon_rings, rings_multipolygon, rings_polygon};
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(points_wkt, m)?)?;
     m.add_function(wrap_pyfunction!(wkt_points, m)?)?;
     m.add_function(wrap_pyfunction!(polygons_wkt, m)?)?;
     m.add_function(wrap_pyfunction!(wkt_polygons, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_rings_wkt, m)?)?;
+    m.add_function(wrap_pyfunction!(wkt_polygons_rings, m)?)?;
+    m.add_function(wrap_pyfunction!(multipolygons_wkt, m)?)?;
+    m.add_function(wrap_pyfunction!(wkt_multipolygons, m)?)?;
     Ok(())
 }
 
@@ -28,17 +34,16 @@ pub fn points_wkt(points: Vec<Point2D>) -> Vec<String> {
 }
 
 #[pyfunction]
-pub fn wkt_points(wkt_strings: Vec<&str>) -> Vec<Point2D> {
+pub fn wkt_points(wkt_strings: Vec<&str>) -> PyResult<Vec<Point2D>> {
     wkt_strings
         .into_iter()
         .map(|w| {
-            let wkt_obj = match Wkt::from_str(w) {
-                Ok(result) => result,
-                Err(_) => panic!("Failed to parse the points, invalid format"),
-            };
-            let p = geo::Point::try_from(wkt_obj).unwrap();
+            let wkt_obj = parse_wkt(w)?;
+            let p = geo::Point::try_from(wkt_obj).map_err(|_| {
+                PyTypeError::new_err(format!("Expect a POINT, got '{}'", w))
+            })?;
             let (x, y) = p.x_y();
-            [x, y]
+            Ok([x, y])
         })
         .collect()
 }
@@ -56,29 +61,95 @@ pub fn polygons_wkt(polygons: Vec<Vec<Point2D>>) -> Vec<String> {
 }
 
 #[pyfunction]
-pub fn wkt_polygons(wkt_strings: Vec<&str>) -> Vec<Vec<Point2D>> {
+pub fn wkt_polygons(wkt_strings: Vec<&str>) -> PyResult<Vec<Vec<Point2D>>> {
     wkt_strings
         .into_iter()
         .map(|w| {
-            let wkt_obj = match Wkt::from_str(w) {
-                Ok(result) => result,
-                Err(_) => panic!("Failed to parse the shapes, invalid format"),
-            };
-            let p = geo::Polygon::try_from(wkt_obj).unwrap();
-            p.exterior()
+            let wkt_obj = parse_wkt(w)?;
+            let p = geo::Polygon::try_from(wkt_obj).map_err(|_| {
+                PyTypeError::new_err(format!("Expect a POLYGON, got '{}'", w))
+            })?;
+            Ok(p.exterior()
                 .points()
                 .map(|ip| {
                     let (x, y) = ip.x_y();
                     [x, y]
                 })
-                .collect()
+                .collect())
         })
         .collect()
 }
 
+// Polygons with holes, the first ring is the exterior
+#[pyfunction]
+pub fn polygons_rings_wkt(polygons: Vec<PolygonRings>) -> PyResult<Vec<String>> {
+    polygons
+        .into_iter()
+        .map(|rings| {
+            let wkt = geo::Geometry::from(rings_polygon(rings)?).to_wkt();
+            Ok(format!("{}", wkt.item))
+        })
+        .collect()
+}
+
+#[pyfunction]
+pub fn wkt_polygons_rings(wkt_strings: Vec<&str>) -> PyResult<Vec<PolygonRings>> {
+    wkt_strings
+        .into_iter()
+        .map(|w| {
+            let wkt_obj = parse_wkt(w)?;
+            let p = geo::Polygon::try_from(wkt_obj).map_err(|_| {
+                PyTypeError::new_err(format!("Expect a POLYGON, got '{}'", w))
+            })?;
+            Ok(polygon_rings(&p))
+        })
+        .collect()
+}
+
+#[pyfunction]
+pub fn multipolygons_wkt(multipolygons: Vec<MultiPolygonRings>) -> PyResult<Vec<String>> {
+    multipolygons
+        .into_iter()
+        .map(|parts| {
+            let wkt = geo::Geometry::from(rings_multipolygon(parts)?).to_wkt();
+            Ok(format!("{}", wkt.item))
+        })
+        .collect()
+}
+
+// Both POLYGON and MULTIPOLYGON are accepted, a POLYGON becomes a multipolygon of one part
+#[pyfunction]
+pub fn wkt_multipolygons(wkt_strings: Vec<&str>) -> PyResult<Vec<MultiPolygonRings>> {
+    wkt_strings
+        .into_iter()
+        .map(|w| {
+            let geom = geo::Geometry::try_from(parse_wkt(w)?).map_err(|_| {
+                PyTypeError::new_err(format!("Expect a POLYGON or MULTIPOLYGON, got '{}'", w))
+            })?;
+            match geom {
+                geo::Geometry::Polygon(p) => Ok(vec![polygon_rings(&p)]),
+                geo::Geometry::MultiPolygon(mp) => Ok(mp.iter().map(polygon_rings).collect()),
+                _ => Err(PyTypeError::new_err(format!(
+                    "Expect a POLYGON or MULTIPOLYGON, got '{}'",
+                    w
+                ))),
+            }
+        })
+        .collect()
+}
+
+fn parse_wkt(w: &str) -> PyResult<Wkt<f64>> {
+    Wkt::from_str(w).map_err(|e| {
+        PyValueError::new_err(format!("Failed to parse '{}', invalid WKT format: {}", w, e))
+    })
+}
+
 #[cfg(test)]
 mod test {
-    use crate::io::{points_wkt, polygons_wkt, wkt_points, wkt_polygons};
+    use crate::io::{
+        multipolygons_wkt, points_wkt, polygons_rings_wkt, polygons_wkt, wkt_multipolygons,
+        wkt_points, wkt_polygons, wkt_polygons_rings,
+    };
 
     #[test]
     fn test_points_wkt() {
@@ -90,7 +161,7 @@ mod test {
     #[test]
     fn test_wkt_points() {
         let points = vec!["POINT(1 2)", "POINT(0 2)"];
-        let wkt = wkt_points(points);
+        let wkt = wkt_points(points).unwrap();
         println!("{:?}", wkt);
     }
 
@@ -104,7 +175,26 @@ mod test {
     #[test]
     fn test_wkt_polygons() {
         let polygons = vec!["POLYGON((0 1,0 2,0 1))"];
-        let wkt = wkt_polygons(polygons);
+        let wkt = wkt_polygons(polygons).unwrap();
         println!("{:?}", wkt);
     }
+
+    #[test]
+    fn test_wkt_rings() {
+        let polygons = vec!["POLYGON((0 0,4 0,4 4,0 4,0 0),(1 1,3 1,3 3,1 3,1 1))"];
+        let rings = wkt_polygons_rings(polygons.clone()).unwrap();
+        assert_eq!(rings[0].len(), 2);
+        assert_eq!(polygons_rings_wkt(rings).unwrap(), polygons);
+
+        let multipolygons = vec![
+            "MULTIPOLYGON(((0 0,1 0,1 1,0 0)),((2 2,3 2,3 3,2 2)))",
+            "POLYGON((0 0,1 0,1 1,0 0))",
+        ];
+        let parts = wkt_multipolygons(multipolygons).unwrap();
+        assert_eq!(parts[0].len(), 2);
+        assert_eq!(parts[1].len(), 1);
+        let wkt = multipolygons_wkt(parts).unwrap();
+        assert_eq!(wkt[0], "MULTIPOLYGON(((0 0,1 0,1 1,0 0)),((2 2,3 2,3 3,2 2)))");
+        assert!(wkt_multipolygons(vec!["POINT(1 2)"]).is_err());
+    }
 }
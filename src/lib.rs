@@ -4,19 +4,26 @@ use counter::Counter;
 use pyo3::prelude::*;
 
 use crate::stat::{mean_f, mean_u, std_f, std_u};
-use crate::utils::{py_kwarg, zscore2pvalue};
+use crate::utils::{check_neighbors, check_same_length, py_kwarg, zscore2pvalue};
 
 mod cell_interaction;
+mod convex_hull_3d;
 mod corr;
 mod custom_type;
+mod delaunay_3d;
 mod distribution_index;
 mod entropy;
 mod geo;
 mod hotspot;
 mod io;
+mod neighbors_contact;
+mod neighbors_csr;
+mod neighbors_graph;
 mod neighbors_search;
+mod predicates;
 mod preprocessing;
 mod quad_stats;
+mod simplify;
 mod spatial_autocorr;
 mod stat;
 mod utils;
@@ -25,6 +32,7 @@ mod utils;
 fn spatialtis_core<'py>(py: Python, m: &PyModule) -> PyResult<()> {
     // geometry processing
     geo::register(py, m)?;
+    simplify::register(py, m)?;
     cell_interaction::register(py, m)?;
     entropy::register(py, m)?;
     // m.add_wrapped(wrap_pyfunction!(points_bbox))?;
@@ -47,6 +55,9 @@ fn spatialtis_core<'py>(py: Python, m: &PyModule) -> PyResult<()> {
 
     // neighbors search
     neighbors_search::register(py, m)?;
+    neighbors_graph::register(py, m)?;
+    neighbors_csr::register(py, m)?;
+    neighbors_contact::register(py, m)?;
     // m.add_wrapped(wrap_pyfunction!(points_neighbors))?;
     // m.add_wrapped(wrap_pyfunction!(bbox_neighbors))?;
 
@@ -70,6 +81,9 @@ fn spatialtis_core<'py>(py: Python, m: &PyModule) -> PyResult<()> {
     // m.add_class::<CellCombs>()?;
     // m.add_wrapped(wrap_pyfunction!(comb_bootstrap))?;
 
+    // label mask preprocessing
+    preprocessing::register(py, m)?;
+
     Ok(())
 }
 
@@ -192,7 +206,9 @@ pub fn neighbor_components(
     neighbors: Vec<Vec<usize>>,
     labels: Vec<usize>,
     types: Vec<&str>,
-) -> (Vec<&str>, Vec<Vec<usize>>) {
+) -> PyResult<(Vec<&str>, Vec<Vec<usize>>)> {
+    check_same_length("labels", labels.len(), "types", types.len())?;
+    check_neighbors(&neighbors, &labels)?;
     let mut uni_types: HashMap<&str, i64> = HashMap::new();
     let mut types_mapper: HashMap<usize, &str> = HashMap::new();
     for (i, t) in labels.iter().zip(types.iter()) {
@@ -222,7 +238,7 @@ pub fn neighbor_components(
         })
         .collect();
 
-    (uni_types, result)
+    Ok((uni_types, result))
 }
 
 // Constructor function
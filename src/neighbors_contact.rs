@@ -0,0 +1,365 @@
+// Cell contact neighbors from the polygon shape of cells
+//
+// The bounding box of each polygon is searched in the R-tree to find the candidates,
+// then the candidates are checked with the exact distance between polygons,
+// the border lines of each polygon are also indexed in an R-tree, only the lines
+// that close to each other are compared.
+use geo::algorithm::bounding_rect::BoundingRect;
+use geo::algorithm::euclidean_distance::EuclideanDistance;
+use geo::algorithm::intersects::Intersects;
+use geo::{Line, LineString, Point, Polygon};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use rstar::primitives::Line as SegmentLine;
+use rstar::{RTree, RTreeObject, AABB};
+
+use crate::custom_type::{BBox, NeighborsDist, Point2D};
+use crate::neighbors_search::{init_bbox, BBox2D};
+use crate::utils::{check_collections, check_points, check_same_length};
+
+// The tolerance for two borders to be considered as coincide
+const CONTACT_EPS: f64 = 1e-8;
+
+// The border lines of a polygon, including the holes
+type Border = RTree<SegmentLine<Point2D>>;
+
+pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(polygons_neighbors_contact, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_neighbors_contact_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_neighbors_contact_with_border, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_neighbors_contact_with_border_parallel, m)?)?;
+    Ok(())
+}
+
+// Two cells are neighbors if their polygons touch, overlap or within `buffer` distance
+// The cell itself is included as other neighbors search methods
+#[pyfunction]
+pub fn polygons_neighbors_contact(
+    polygons: Vec<Vec<Point2D>>,
+    labels: Vec<usize>,
+    buffer: f64,
+) -> PyResult<Vec<Vec<usize>>> {
+    let polygons = check_polygons(polygons, &labels, buffer)?;
+    let borders: Vec<Border> = polygons.par_iter().map(polygon_border).collect();
+    Ok(contact_pairs(&polygons, &borders, buffer)
+        .into_iter()
+        .map(|neighs| neighs.into_iter().map(|j| labels[j]).collect())
+        .collect())
+}
+
+#[pyfunction]
+pub fn polygons_neighbors_contact_parallel(
+    polygons_collections: Vec<Vec<Vec<Point2D>>>,
+    labels_collections: Vec<Vec<usize>>,
+    buffer: f64,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&polygons_collections, &labels_collections)?;
+    polygons_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| polygons_neighbors_contact(ps, ls, buffer))
+        .collect()
+}
+
+// Same as `polygons_neighbors_contact`, the length of the shared border is returned,
+// which is the length of the cell border that lies within `buffer` of the neighbor border,
+// the shared border of the cell itself is 0
+#[pyfunction]
+pub fn polygons_neighbors_contact_with_border(
+    polygons: Vec<Vec<Point2D>>,
+    labels: Vec<usize>,
+    buffer: f64,
+) -> PyResult<NeighborsDist> {
+    let polygons = check_polygons(polygons, &labels, buffer)?;
+    let borders: Vec<Border> = polygons.par_iter().map(polygon_border).collect();
+    let pairs = contact_pairs(&polygons, &borders, buffer);
+    Ok(pairs
+        .into_par_iter()
+        .enumerate()
+        .map(|(i, neighs)| {
+            neighs
+                .into_iter()
+                .map(|j| {
+                    let border = if i == j {
+                        0.0
+                    } else {
+                        shared_border_length(&borders[i], &borders[j], buffer + CONTACT_EPS)
+                    };
+                    (labels[j], border)
+                })
+                .unzip()
+        })
+        .unzip())
+}
+
+#[pyfunction]
+pub fn polygons_neighbors_contact_with_border_parallel(
+    polygons_collections: Vec<Vec<Vec<Point2D>>>,
+    labels_collections: Vec<Vec<usize>>,
+    buffer: f64,
+) -> PyResult<Vec<NeighborsDist>> {
+    check_collections(&polygons_collections, &labels_collections)?;
+    polygons_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| polygons_neighbors_contact_with_border(ps, ls, buffer))
+        .collect()
+}
+
+fn check_polygons(
+    polygons: Vec<Vec<Point2D>>,
+    labels: &[usize],
+    buffer: f64,
+) -> PyResult<Vec<Polygon<f64>>> {
+    check_same_length("polygons", polygons.len(), "labels", labels.len())?;
+    if !buffer.is_finite() || (buffer < 0.0) {
+        return Err(PyValueError::new_err(format!(
+            "buffer must be a non-negative number, got {}",
+            buffer
+        )));
+    }
+    polygons
+        .into_iter()
+        .zip(labels)
+        .map(|(p, l)| {
+            if p.len() < 3 {
+                return Err(PyValueError::new_err(format!(
+                    "The polygon of cell {} has less than 3 points",
+                    l
+                )));
+            }
+            check_points(&p)?;
+            Ok(Polygon::new(LineString::from(p), vec![]))
+        })
+        .collect()
+}
+
+// The index of the polygons that within `buffer` of each polygon
+pub fn contact_pairs(polygons: &[Polygon<f64>], borders: &[Border], buffer: f64) -> Vec<Vec<usize>> {
+    let bbox: Vec<BBox> = polygons
+        .iter()
+        .map(|p| {
+            let rect = p.bounding_rect().unwrap();
+            (rect.min().x, rect.min().y, rect.max().x, rect.max().y)
+        })
+        .collect();
+    let envelopes: Vec<AABB<[f64; 2]>> = bbox
+        .iter()
+        .map(|b| {
+            AABB::from_corners(
+                [b.0 - buffer - CONTACT_EPS, b.1 - buffer - CONTACT_EPS],
+                [b.2 + buffer + CONTACT_EPS, b.3 + buffer + CONTACT_EPS],
+            )
+        })
+        .collect();
+    let tree: RTree<BBox2D> = RTree::bulk_load(init_bbox(bbox, (0..polygons.len()).collect()));
+    envelopes
+        .par_iter()
+        .enumerate()
+        .map(|(i, aabb)| {
+            let mut neighs: Vec<usize> = tree
+                .locate_in_envelope_intersecting(aabb)
+                .map(|b| b.label)
+                .filter(|j| {
+                    (*j == i)
+                        || polygons_within(
+                            (&polygons[i], &borders[i]),
+                            (&polygons[*j], &borders[*j]),
+                            buffer + CONTACT_EPS,
+                        )
+                })
+                .collect();
+            neighs.sort_unstable();
+            neighs
+        })
+        .collect()
+}
+
+pub fn polygon_border(polygon: &Polygon<f64>) -> Border {
+    let lines = std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .flat_map(|ring| ring.lines())
+        .map(|line| SegmentLine::new(line.start.into(), line.end.into()))
+        .collect();
+    RTree::bulk_load(lines)
+}
+
+// If the distance between two polygons is within `dist`, it's 0 if they overlap
+// geo measures the border distance from the vertices of one side only, so the pairs of
+// border lines are compared here
+fn polygons_within(
+    (poly, border): (&Polygon<f64>, &Border),
+    (other, other_border): (&Polygon<f64>, &Border),
+    dist: f64,
+) -> bool {
+    let close_lines = border.iter().any(|line| {
+        let l = as_line(line);
+        other_border
+            .locate_in_envelope_intersecting(&expand(line.envelope(), dist))
+            .any(|o| l.euclidean_distance(&as_line(o)) <= dist)
+    });
+    // The borders don't touch, one is inside the other if any vertex is inside
+    close_lines
+        || other.intersects(&Point::from(poly.exterior().0[0]))
+        || poly.intersects(&Point::from(other.exterior().0[0]))
+}
+
+// The length of the border that within `tol` of the other border
+pub fn shared_border_length(border: &Border, other: &Border, tol: f64) -> f64 {
+    border
+        .iter()
+        .map(|line| {
+            let mut intervals: Vec<(f64, f64)> = other
+                .locate_in_envelope_intersecting(&expand(line.envelope(), tol))
+                .filter_map(|o| close_interval(line, o, tol))
+                .collect();
+            intervals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            // The total length of the union of intervals
+            let mut covered = 0.0;
+            let mut end = 0.0;
+            for (lo, hi) in intervals {
+                let lo = lo.max(end);
+                if hi > lo {
+                    covered += hi - lo;
+                    end = hi;
+                }
+            }
+            covered * line.length_2().sqrt()
+        })
+        .sum()
+}
+
+// The parameter interval of `line` that within `tol` of `other`, the region within `tol`
+// of a line is a rectangle capped by two discs, which is convex, so the interval is
+// the union of the intervals that the line crosses the three parts
+fn close_interval(
+    line: &SegmentLine<Point2D>,
+    other: &SegmentLine<Point2D>,
+    tol: f64,
+) -> Option<(f64, f64)> {
+    let (s, d) = (line.from, sub(&line.to, &line.from));
+    let dd = dot(&d, &d);
+    if dd == 0.0 {
+        return None;
+    }
+    // The line crosses the disc of radius `tol` around `c`
+    let disc = |c: &Point2D| {
+        let sc = sub(&s, c);
+        let (b, c) = (dot(&d, &sc), dot(&sc, &sc) - tol * tol);
+        let delta = b * b - dd * c;
+        (delta >= 0.0).then(|| ((-b - delta.sqrt()) / dd, (-b + delta.sqrt()) / dd))
+    };
+    let (a, e) = (other.from, sub(&other.to, &other.from));
+    let ee = dot(&e, &e);
+    let mut parts = vec![disc(&other.from), disc(&other.to)];
+    if ee > 0.0 {
+        // The projection on `other` is within the line, and the distance to it is within `tol`
+        let sa = sub(&s, &a);
+        let normal = [-e[1] / ee.sqrt(), e[0] / ee.sqrt()];
+        parts.push(
+            linear_interval(dot(&sa, &e), dot(&d, &e), 0.0, ee).and_then(|(lo, hi)| {
+                let (lo2, hi2) = linear_interval(dot(&sa, &normal), dot(&d, &normal), -tol, tol)?;
+                (lo.max(lo2) <= hi.min(hi2)).then(|| (lo.max(lo2), hi.min(hi2)))
+            }),
+        );
+    }
+    let (lo, hi) = parts
+        .into_iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (l, h)| (lo.min(l), hi.max(h)));
+    let (lo, hi) = (lo.max(0.0), hi.min(1.0));
+    (lo <= hi).then_some((lo, hi))
+}
+
+// The interval of t that `lo <= a + b * t <= hi`
+fn linear_interval(a: f64, b: f64, lo: f64, hi: f64) -> Option<(f64, f64)> {
+    if b == 0.0 {
+        return ((lo <= a) && (a <= hi)).then_some((f64::NEG_INFINITY, f64::INFINITY));
+    }
+    let (t1, t2) = ((lo - a) / b, (hi - a) / b);
+    Some((t1.min(t2), t1.max(t2)))
+}
+
+fn as_line(line: &SegmentLine<Point2D>) -> Line<f64> {
+    Line::new(line.from, line.to)
+}
+
+fn expand(envelope: AABB<Point2D>, dist: f64) -> AABB<Point2D> {
+    let (lower, upper) = (envelope.lower(), envelope.upper());
+    AABB::from_corners(
+        [lower[0] - dist, lower[1] - dist],
+        [upper[0] + dist, upper[1] + dist],
+    )
+}
+
+fn sub(a: &Point2D, b: &Point2D) -> Point2D {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn dot(a: &Point2D, b: &Point2D) -> f64 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use geo::{LineString, Polygon};
+
+    use crate::neighbors_contact::{contact_pairs, polygon_border, shared_border_length};
+
+    fn rect(minx: f64, miny: f64, maxx: f64, maxy: f64) -> Polygon<f64> {
+        Polygon::new(
+            LineString::from(vec![
+                [minx, miny],
+                [maxx, miny],
+                [maxx, maxy],
+                [minx, maxy],
+                [minx, miny],
+            ]),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_contact_pairs() {
+        // Two long thin cells side by side, and a third one 0.5 away
+        let polygons = vec![
+            rect(0.0, 0.0, 10.0, 1.0),
+            rect(2.0, 1.0, 6.0, 2.0),
+            rect(0.0, 2.5, 10.0, 3.5),
+        ];
+        let borders: Vec<_> = polygons.iter().map(polygon_border).collect();
+        let pairs = contact_pairs(&polygons, &borders, 0.0);
+        assert_eq!(pairs, vec![vec![0, 1], vec![0, 1], vec![2]]);
+        let pairs = contact_pairs(&polygons, &borders, 0.5);
+        assert_eq!(pairs, vec![vec![0, 1], vec![0, 1, 2], vec![1, 2]]);
+
+        let border = shared_border_length(&borders[0], &borders[1], 1e-8);
+        assert!((border - 4.0).abs() < 1e-6);
+        let border = shared_border_length(&borders[1], &borders[0], 1e-8);
+        assert!((border - 4.0).abs() < 1e-6);
+        // The border of 1 within 1.0 of 2: the top edge and the upper half of the sides
+        let border = shared_border_length(&borders[1], &borders[2], 1.0);
+        assert!((border - 5.0).abs() < 1e-9);
+        // A tilted edge, only the part within the tolerance is counted
+        let tilted = Polygon::new(
+            LineString::from(vec![[0.0, 3.0], [4.0, 3.0], [0.0, 2.5], [0.0, 3.0]]),
+            vec![],
+        );
+        let border = shared_border_length(&polygon_border(&tilted), &borders[2], 0.25);
+        // The left edge lies on the border of 2, the top edge is close to it at the left end,
+        // the tilted edge rises 0.5 away from the bottom of 2, half of it is counted
+        assert!((border - (0.5 + 0.25 + 4.0f64.hypot(0.5) / 2.0)).abs() < 1e-9);
+
+        // A cell inside the hole of another cell is not a neighbor
+        let ring = Polygon::new(
+            rect(0.0, 0.0, 10.0, 10.0).exterior().to_owned(),
+            vec![rect(2.0, 2.0, 8.0, 8.0).exterior().to_owned()],
+        );
+        let polygons = vec![ring, rect(4.0, 4.0, 6.0, 6.0), rect(12.0, 0.0, 13.0, 1.0)];
+        let borders: Vec<_> = polygons.iter().map(polygon_border).collect();
+        assert_eq!(contact_pairs(&polygons, &borders, 1.0)[1], vec![1]);
+        assert_eq!(contact_pairs(&polygons, &borders, 2.0)[1], vec![0, 1]);
+        assert_eq!(contact_pairs(&polygons, &borders, 2.0)[2], vec![0, 2]);
+    }
+}
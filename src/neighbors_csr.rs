@@ -0,0 +1,583 @@
+// Neighbors search that read from numpy array directly, and return in CSR format
+// (indptr, indices, distances), the indices are the labels of neighbors,
+// which can be used to construct scipy sparse matrix without converting python list
+use std::borrow::Cow;
+
+use kiddo::distance::squared_euclidean;
+use ndarray::{ArrayView1, ArrayView2};
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::custom_type::Point2D;
+use crate::neighbors_search::{kdtree_builder, query_point, triangulation_neighbors};
+use crate::spatial_autocorr::label_columns;
+use crate::utils::check_points;
+
+type CsrArrays<'py> = (&'py PyArray1<i64>, &'py PyArray1<i64>, Option<&'py PyArray1<f64>>);
+type WeightsArrays<'py> = (usize, &'py PyArray1<i64>, &'py PyArray1<i64>, &'py PyArray1<f64>);
+
+pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_csr, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_triangulation_csr, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_grid_csr, m)?)?;
+    m.add_function(wrap_pyfunction!(spatial_weights_csr, m)?)?;
+    Ok(())
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_csr<'py>(
+    py: Python<'py>,
+    points: PyReadonlyArray2<f64>,
+    labels: PyReadonlyArray1<i64>,
+    r: f64,
+    k: usize,
+    return_distance: bool,
+) -> PyResult<CsrArrays<'py>> {
+    let points = points.as_array();
+    let labels = array_labels(labels, points.nrows())?;
+    let (indptr, indices, distances) = py.allow_threads(|| match points.ncols() {
+        2 => kdtree_csr::<2>(points, &labels, r, k, return_distance),
+        3 => kdtree_csr::<3>(points, &labels, r, k, return_distance),
+        d => Err(PyValueError::new_err(format!(
+            "Only support 2D and 3D points, got {}D points",
+            d
+        ))),
+    })?;
+    Ok(csr_arrays(py, indptr, indices, distances, return_distance))
+}
+
+#[pyfunction]
+pub fn points_neighbors_triangulation_csr<'py>(
+    py: Python<'py>,
+    points: PyReadonlyArray2<f64>,
+    labels: PyReadonlyArray1<i64>,
+) -> PyResult<CsrArrays<'py>> {
+    let points = points.as_array();
+    if points.ncols() != 2 {
+        return Err(PyValueError::new_err(format!(
+            "Delaunay triangulation only support 2D points, got {}D points",
+            points.ncols()
+        )));
+    }
+    let labels = array_labels(labels, points.nrows())?;
+    let points: Cow<[Point2D]> = array_points(points);
+    check_points(&points)?;
+    let neighbors = py.allow_threads(|| triangulation_neighbors(&points, &labels, None, None, false));
+
+    let mut indptr = Vec::with_capacity(neighbors.len() + 1);
+    let mut indices = vec![];
+    indptr.push(0);
+    for neighs in neighbors {
+        indices.extend(neighs.into_iter().map(|i| i as i64));
+        indptr.push(indices.len() as i64);
+    }
+    Ok(csr_arrays(py, indptr, indices, vec![], false))
+}
+
+// Radius search with uniform grid spatial hash, for slides with millions of cells
+// The results are the same as `points_neighbors_kdtree_csr` with `r`,
+// the neighbors of each point are sorted by distance.
+// The points are split into tiles of `tile_size`, each tile is searched with a halo
+// of one grid cell, tiles are processed in parallel and keep the neighbors in flat arrays,
+// without the per point lists of the KD-tree search.
+#[pyfunction]
+pub fn points_neighbors_grid_csr<'py>(
+    py: Python<'py>,
+    points: PyReadonlyArray2<f64>,
+    labels: PyReadonlyArray1<i64>,
+    r: f64,
+    tile_size: Option<f64>,
+    return_distance: bool,
+) -> PyResult<CsrArrays<'py>> {
+    if !r.is_finite() || (r <= 0.0) {
+        return Err(PyValueError::new_err(format!(
+            "Grid search requires a positive radius, got r={}",
+            r
+        )));
+    }
+    let tile_size = tile_size.unwrap_or(r * DEFAULT_TILE_CELLS as f64);
+    if tile_size.is_nan() || (tile_size < r) {
+        return Err(PyValueError::new_err(format!(
+            "tile_size must not be smaller than r, got tile_size={} and r={}",
+            tile_size, r
+        )));
+    }
+    let tile_cells = (tile_size / r).floor().min(MAX_GRID_CELLS as f64) as i64;
+    let points = points.as_array();
+    let labels = array_labels(labels, points.nrows())?;
+    let (indptr, indices, distances) = match points.ncols() {
+        2 => {
+            let points: Cow<[[f64; 2]]> = array_points(points);
+            check_points(&points)?;
+            check_grid_extent(&points, r)?;
+            py.allow_threads(|| grid_csr(&points, &labels, r, tile_cells, return_distance))
+        }
+        3 => {
+            let points: Cow<[[f64; 3]]> = array_points(points);
+            check_points(&points)?;
+            check_grid_extent(&points, r)?;
+            py.allow_threads(|| grid_csr(&points, &labels, r, tile_cells, return_distance))
+        }
+        d => {
+            return Err(PyValueError::new_err(format!(
+                "Only support 2D and 3D points, got {}D points",
+                d
+            )))
+        }
+    };
+    Ok(csr_arrays(py, indptr, indices, distances, return_distance))
+}
+
+// Row-normalized spatial weights from neighbors in CSR format
+// The column of the matrix is the position of the label in `labels`,
+// same as `build_neighbors_matrix`
+#[pyfunction]
+#[pyo3(name = "build_neighbors_matrix_csr")]
+pub fn spatial_weights_csr<'py>(
+    py: Python<'py>,
+    indptr: PyReadonlyArray1<i64>,
+    indices: PyReadonlyArray1<i64>,
+    labels: PyReadonlyArray1<i64>,
+) -> PyResult<WeightsArrays<'py>> {
+    let indptr = indptr.as_array();
+    let indices = indices.as_array();
+    let n = indptr.len().saturating_sub(1);
+    let labels = array_labels(labels, n)?;
+    let labels: Vec<i64> = labels.into_iter().map(|l| l as i64).collect();
+    let (indptr, indices, data) = weights_csr(indptr, indices, &labels)?;
+    Ok((
+        n,
+        indptr.into_pyarray(py),
+        indices.into_pyarray(py),
+        data.into_pyarray(py),
+    ))
+}
+
+// The indptr may be a slice of a larger CSR, it's shifted to start from 0
+// to match the new indices
+fn weights_csr(
+    indptr: ArrayView1<i64>,
+    indices: ArrayView1<i64>,
+    labels: &[i64],
+) -> PyResult<(Vec<i64>, Vec<i64>, Vec<f64>)> {
+    let columns = label_columns(labels)?;
+    let offset = indptr.first().copied().unwrap_or(0);
+    let mut new_indptr = Vec::with_capacity(indptr.len());
+    let mut new_indices = Vec::with_capacity(indices.len());
+    let mut data = Vec::with_capacity(indices.len());
+    new_indptr.push(0);
+    for ((start, end), l) in indptr.iter().zip(indptr.iter().skip(1)).zip(labels) {
+        let (start, end) = (*start as usize, *end as usize);
+        if (start > end) | (end > indices.len()) {
+            return Err(PyValueError::new_err("Invalid indptr for the indices"));
+        }
+        let neighs = indices.slice(ndarray::s![start..end]);
+        if let Some(n) = neighs.iter().find(|n| !columns.contains_key(n)) {
+            return Err(PyValueError::new_err(format!(
+                "The neighbor {} of cell {} is not found in labels",
+                n, l
+            )));
+        }
+        let mut neighs: Vec<i64> = neighs
+            .iter()
+            .map(|i| columns[i] as i64)
+            .collect();
+        neighs.sort_unstable();
+        let weights = 1.0 / (neighs.len() as f64);
+        data.resize(data.len() + neighs.len(), weights);
+        new_indices.extend(neighs);
+        new_indptr.push(end as i64 - offset);
+    }
+    Ok((new_indptr, new_indices, data))
+}
+
+fn kdtree_csr<const K: usize>(
+    points: ArrayView2<f64>,
+    labels: &[usize],
+    r: f64,
+    k: usize,
+    return_distance: bool,
+) -> PyResult<(Vec<i64>, Vec<i64>, Vec<f64>)> {
+    let points: Cow<[[f64; K]]> = array_points(points);
+    let tree = kdtree_builder(&points, labels)?;
+    let results: Vec<Vec<(f64, usize)>> = points
+        .par_iter()
+        .map(|p| query_point(&tree, p, r, k))
+        .collect();
+
+    let size: usize = results.iter().map(|n| n.len()).sum();
+    let mut indptr = Vec::with_capacity(results.len() + 1);
+    let mut indices = Vec::with_capacity(size);
+    let mut distances = if return_distance {
+        Vec::with_capacity(size)
+    } else {
+        vec![]
+    };
+    indptr.push(0);
+    for neighs in results {
+        for (d, i) in neighs {
+            indices.push(i as i64);
+            if return_distance {
+                distances.push(d.sqrt());
+            }
+        }
+        indptr.push(indices.len() as i64);
+    }
+    Ok((indptr, indices, distances))
+}
+
+// The default side of a tile, in the number of grid cells
+const DEFAULT_TILE_CELLS: usize = 64;
+
+// The largest cell index along an axis, the cells and tiles are exact in f64
+// and their neighbors never overflow i64
+const MAX_GRID_CELLS: i64 = 1 << 52;
+
+fn check_grid_extent<const K: usize>(points: &[[f64; K]], r: f64) -> PyResult<()> {
+    for d in 0..K {
+        let (lo, hi) = points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+            (lo.min(p[d]), hi.max(p[d]))
+        });
+        if (hi - lo) / r > MAX_GRID_CELLS as f64 {
+            return Err(PyValueError::new_err(format!(
+                "The extent of points ({}) is too large for the grid of r={}, use a larger r",
+                hi - lo,
+                r
+            )));
+        }
+    }
+    Ok(())
+}
+
+// A uniform grid that each point is hashed into a cell of side `r`,
+// all the neighbors within `r` are in the adjacent cells
+struct UniformGrid<const K: usize> {
+    // The cell of each point
+    cells: Vec<[i64; K]>,
+    // Point index sorted by (tile, cell)
+    order: Vec<usize>,
+    // The unique tiles and their range in `order`
+    tiles: Vec<([i64; K], usize, usize)>,
+    tile_cells: i64,
+}
+
+impl<const K: usize> UniformGrid<K> {
+    fn new(points: &[[f64; K]], r: f64, tile_cells: i64) -> UniformGrid<K> {
+        let mut origin = [f64::INFINITY; K];
+        for p in points {
+            for d in 0..K {
+                origin[d] = origin[d].min(p[d]);
+            }
+        }
+        // Slightly larger than r, so the rounding error never puts two neighbors two cells away
+        let side = r * (1.0 + 1e-9);
+        let cells: Vec<[i64; K]> = points
+            .iter()
+            .map(|p| {
+                let mut c = [0; K];
+                for d in 0..K {
+                    c[d] = ((p[d] - origin[d]) / side).floor() as i64;
+                }
+                c
+            })
+            .collect();
+        let tile_of = |c: &[i64; K]| {
+            let mut t = [0; K];
+            for d in 0..K {
+                t[d] = c[d].div_euclid(tile_cells);
+            }
+            t
+        };
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.par_sort_unstable_by_key(|i| (tile_of(&cells[*i]), cells[*i]));
+
+        let mut tiles = vec![];
+        let mut start = 0;
+        for end in 1..=order.len() {
+            if (end == order.len()) || (tile_of(&cells[order[end]]) != tile_of(&cells[order[start]])) {
+                tiles.push((tile_of(&cells[order[start]]), start, end));
+                start = end;
+            }
+        }
+        UniformGrid {
+            cells,
+            order,
+            tiles,
+            tile_cells,
+        }
+    }
+
+    // The points in the tile and the halo around it, sorted by cell
+    fn tile_candidates(&self, tile: &[i64; K]) -> Vec<([i64; K], usize)> {
+        let mut candidates = vec![];
+        for offset in neighbor_offsets::<K>() {
+            let mut key = [0; K];
+            for d in 0..K {
+                key[d] = tile[d] + offset[d];
+            }
+            let ix = match self.tiles.binary_search_by_key(&key, |(t, _, _)| *t) {
+                Ok(ix) => ix,
+                Err(_) => continue,
+            };
+            let (_, start, end) = self.tiles[ix];
+            for i in &self.order[start..end] {
+                let c = self.cells[*i];
+                let in_halo = (0..K).all(|d| {
+                    (c[d] >= tile[d] * self.tile_cells - 1)
+                        & (c[d] <= (tile[d] + 1) * self.tile_cells)
+                });
+                if in_halo {
+                    candidates.push((c, *i));
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates
+    }
+
+    // The neighbors of each point in the tile, in the order of the points in the tile
+    fn tile_neighbors(
+        &self,
+        points: &[[f64; K]],
+        labels: &[usize],
+        tile: usize,
+        r2: f64,
+        return_distance: bool,
+    ) -> TileRows {
+        let (key, start, end) = self.tiles[tile];
+        let candidates = self.tile_candidates(&key);
+        let mut rows = TileRows {
+            counts: Vec::with_capacity(end - start),
+            indices: vec![],
+            distances: vec![],
+        };
+        for i in &self.order[start..end] {
+            let neighbors = self.point_neighbors(points, &candidates, *i, r2);
+            rows.counts.push(neighbors.len());
+            for (d, j) in neighbors {
+                rows.indices.push(labels[j] as i64);
+                if return_distance {
+                    rows.distances.push(d.sqrt());
+                }
+            }
+        }
+        rows
+    }
+
+    // The neighbors of a point sorted by distance, (squared distance, point index)
+    fn point_neighbors(
+        &self,
+        points: &[[f64; K]],
+        candidates: &[([i64; K], usize)],
+        i: usize,
+        r2: f64,
+    ) -> Vec<(f64, usize)> {
+        let c = self.cells[i];
+        let mut neighbors = vec![];
+        for offset in neighbor_offsets::<K>() {
+            let mut cell = [0; K];
+            for d in 0..K {
+                cell[d] = c[d] + offset[d];
+            }
+            let lo = candidates.partition_point(|(x, _)| *x < cell);
+            let hi = candidates.partition_point(|(x, _)| *x <= cell);
+            for (_, j) in &candidates[lo..hi] {
+                let d = squared_euclidean(&points[i], &points[*j]);
+                if d <= r2 {
+                    neighbors.push((d, *j));
+                }
+            }
+        }
+        neighbors.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+        neighbors
+    }
+}
+
+// The neighbors of the points in a tile, flattened with the number of neighbors of each point
+struct TileRows {
+    counts: Vec<usize>,
+    indices: Vec<i64>,
+    distances: Vec<f64>,
+}
+
+// All the offsets to the adjacent cells, including the cell itself
+fn neighbor_offsets<const K: usize>() -> Vec<[i64; K]> {
+    (0..3_usize.pow(K as u32))
+        .map(|mut i| {
+            let mut offset = [0; K];
+            for o in offset.iter_mut() {
+                *o = (i % 3) as i64 - 1;
+                i /= 3;
+            }
+            offset
+        })
+        .collect()
+}
+
+// The tiles are searched once in parallel, each tile keeps its neighbors in flat arrays,
+// which are copied to the CSR arrays in the order of points and freed tile by tile
+fn grid_csr<const K: usize>(
+    points: &[[f64; K]],
+    labels: &[usize],
+    r: f64,
+    tile_cells: i64,
+    return_distance: bool,
+) -> (Vec<i64>, Vec<i64>, Vec<f64>) {
+    let n = points.len();
+    let mut indptr = vec![0_i64; n + 1];
+    if n == 0 {
+        return (indptr, vec![], vec![]);
+    }
+    let grid = UniformGrid::new(points, r, tile_cells);
+    let r2 = r * r;
+    let rows: Vec<TileRows> = (0..grid.tiles.len())
+        .into_par_iter()
+        .map(|t| grid.tile_neighbors(points, labels, t, r2, return_distance))
+        .collect();
+    for ((_, start, end), tile) in grid.tiles.iter().zip(&rows) {
+        for (i, count) in grid.order[*start..*end].iter().zip(&tile.counts) {
+            indptr[i + 1] = *count as i64;
+        }
+    }
+    for i in 0..n {
+        indptr[i + 1] += indptr[i];
+    }
+
+    let size = indptr[n] as usize;
+    let mut indices = vec![0_i64; size];
+    let mut distances = if return_distance {
+        vec![0.0; size]
+    } else {
+        vec![]
+    };
+    for ((_, start, end), tile) in grid.tiles.iter().zip(rows) {
+        let mut offset = 0;
+        for (i, count) in grid.order[*start..*end].iter().zip(tile.counts) {
+            let row = indptr[*i] as usize;
+            indices[row..row + count].copy_from_slice(&tile.indices[offset..offset + count]);
+            if return_distance {
+                distances[row..row + count]
+                    .copy_from_slice(&tile.distances[offset..offset + count]);
+            }
+            offset += count;
+        }
+    }
+    (indptr, indices, distances)
+}
+
+fn csr_arrays(
+    py: Python,
+    indptr: Vec<i64>,
+    indices: Vec<i64>,
+    distances: Vec<f64>,
+    return_distance: bool,
+) -> CsrArrays {
+    let distances = if return_distance {
+        Some(distances.into_pyarray(py))
+    } else {
+        None
+    };
+    (indptr.into_pyarray(py), indices.into_pyarray(py), distances)
+}
+
+// Read the points from the numpy array without a copy when it's C-contiguous,
+// the caller makes sure the array has K columns
+pub fn array_points<const K: usize>(points: ArrayView2<f64>) -> Cow<[[f64; K]]> {
+    match points.to_slice() {
+        Some(data) if points.ncols() == K => {
+            // SAFETY: [f64; K] has the same layout as K consecutive f64,
+            // a C-contiguous array of K columns is a slice of rows
+            Cow::Borrowed(unsafe {
+                std::slice::from_raw_parts(data.as_ptr() as *const [f64; K], points.nrows())
+            })
+        }
+        _ => points
+            .outer_iter()
+            .map(|row| {
+                let mut p = [0.0; K];
+                for (d, v) in row.iter().take(K).enumerate() {
+                    p[d] = *v;
+                }
+                p
+            })
+            .collect(),
+    }
+}
+
+pub fn array_labels(labels: PyReadonlyArray1<i64>, n: usize) -> PyResult<Vec<usize>> {
+    let labels = labels.as_array();
+    if labels.len() != n {
+        return Err(PyValueError::new_err(format!(
+            "The length of labels ({}) does not match the number of points ({})",
+            labels.len(),
+            n
+        )));
+    }
+    labels
+        .iter()
+        .map(|l| {
+            usize::try_from(*l).map_err(|_| {
+                PyValueError::new_err(format!("Labels must be non-negative, got {}", l))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use ndarray::{array, Array2};
+
+    use crate::neighbors_csr::{array_points, check_grid_extent, grid_csr, kdtree_csr, weights_csr};
+
+    #[test]
+    fn test_grid_csr() {
+        // A jittered lattice, with points on the tiles boundary
+        let n = 400;
+        let points = Array2::from_shape_fn((n, 2), |(i, d)| {
+            let v = if d == 0 { (i % 20) as f64 } else { (i / 20) as f64 };
+            v * 0.7 + ((i * 7 + d * 13) % 10) as f64 * 0.01
+        });
+        let labels: Vec<usize> = (0..n).map(|i| i + 100).collect();
+        let (kd_indptr, kd_indices, _) = kdtree_csr::<2>(points.view(), &labels, 1.5, 0, false).unwrap();
+        let grid_points = array_points::<2>(points.view());
+        assert!(matches!(grid_points, Cow::Borrowed(_)));
+        // A transposed view is not contiguous, the points are copied
+        let transposed = points.t().to_owned();
+        assert_eq!(array_points::<2>(transposed.t()), grid_points);
+        for tile_cells in [1, 3, 64] {
+            let (indptr, indices, distances) = grid_csr(&grid_points, &labels, 1.5, tile_cells, true);
+            assert_eq!(indptr, kd_indptr);
+            assert_eq!(distances.len(), indices.len());
+            for i in 0..n {
+                let (start, end) = (indptr[i] as usize, indptr[i + 1] as usize);
+                let mut expected = kd_indices[start..end].to_vec();
+                expected.sort_unstable();
+                let mut result = indices[start..end].to_vec();
+                result.sort_unstable();
+                assert_eq!(result, expected);
+                // Sorted by distance, the first neighbor is the point itself
+                assert_eq!(indices[start], labels[i] as i64);
+                assert!(distances[start..end].windows(2).all(|w| w[0] <= w[1]));
+            }
+        }
+
+        // The cell index of a far point doesn't fit in the grid
+        assert!(check_grid_extent(&grid_points, 1.5).is_ok());
+        assert!(check_grid_extent(&[[0.0, 0.0], [1e300, 0.0]], 1.5).is_err());
+    }
+
+    #[test]
+    fn test_weights_csr() {
+        // Rows 1 and 2 of a larger CSR, indptr doesn't start from 0
+        let indptr = array![2, 4, 5];
+        let indices = array![9, 9, 10, 20, 20];
+        let (indptr, indices, data) = weights_csr(indptr.view(), indices.view(), &[10, 20]).unwrap();
+        assert_eq!(indptr, vec![0, 2, 3]);
+        assert_eq!(indices, vec![0, 1, 1]);
+        assert_eq!(data, vec![0.5, 0.5, 1.0]);
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::{HashMap, HashSet};
+
+use ordered_float::OrderedFloat;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::custom_type::NeighborsHops;
+use crate::utils::{check_collections, check_same_length, py_kwarg};
+
+pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(neighbors_symmetric, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_symmetric_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_remove_self, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_remove_self_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_cap_degree, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_cap_degree_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_khop, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_khop_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_khop_with_hops, m)?)?;
+    m.add_function(wrap_pyfunction!(neighbors_khop_with_hops_parallel, m)?)?;
+    Ok(())
+}
+
+// Make the neighbors relationship symmetric
+//   union: If A is neighbor of B, B is added as neighbor of A
+//   intersection: Only keep the mutual neighbors
+// The neighbors that are not in the labels are kept in union mode, and removed in intersection mode
+#[pyfunction]
+pub fn neighbors_symmetric(
+    neighbors: Vec<Vec<usize>>,
+    labels: Vec<usize>,
+    mode: Option<&str>,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_same_length("neighbors", neighbors.len(), "labels", labels.len())?;
+    match py_kwarg(mode, "union") {
+        "intersection" => Ok(mutual_neighbors(&neighbors, &labels)),
+        "union" => Ok(union_neighbors(&neighbors, &labels)),
+        mode => Err(PyValueError::new_err(format!(
+            "mode must be 'union' or 'intersection', got '{}'",
+            mode
+        ))),
+    }
+}
+
+#[pyfunction]
+pub fn neighbors_symmetric_parallel(
+    neighbors_collections: Vec<Vec<Vec<usize>>>,
+    labels_collections: Vec<Vec<usize>>,
+    mode: Option<&str>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&neighbors_collections, &labels_collections)?;
+    neighbors_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ns, ls)| neighbors_symmetric(ns, ls, mode))
+        .collect()
+}
+
+// Remove the cell itself from its neighbors
+#[pyfunction]
+pub fn neighbors_remove_self(
+    neighbors: Vec<Vec<usize>>,
+    labels: Vec<usize>,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_same_length("neighbors", neighbors.len(), "labels", labels.len())?;
+    Ok(neighbors
+        .into_iter()
+        .zip(labels)
+        .map(|(neighs, l)| neighs.into_iter().filter(|n| *n != l).collect())
+        .collect())
+}
+
+#[pyfunction]
+pub fn neighbors_remove_self_parallel(
+    neighbors_collections: Vec<Vec<Vec<usize>>>,
+    labels_collections: Vec<Vec<usize>>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&neighbors_collections, &labels_collections)?;
+    neighbors_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ns, ls)| neighbors_remove_self(ns, ls))
+        .collect()
+}
+
+// Keep at most `max_degree` neighbors for each cell
+// If distances are provided, the nearest neighbors are kept,
+// otherwise the first neighbors in the list are kept
+#[pyfunction]
+pub fn neighbors_cap_degree(
+    neighbors: Vec<Vec<usize>>,
+    max_degree: usize,
+    distances: Option<Vec<Vec<f64>>>,
+) -> PyResult<Vec<Vec<usize>>> {
+    Ok(match distances {
+        Some(distances) => {
+            check_distances(&neighbors, &distances)?;
+            neighbors
+                .into_iter()
+                .zip(distances)
+                .map(|(neighs, dists)| {
+                    let mut pairs: Vec<(f64, usize)> = dists.into_iter().zip(neighs).collect();
+                    pairs.sort_by_key(|(d, _)| OrderedFloat(*d));
+                    pairs.into_iter().take(max_degree).map(|(_, n)| n).collect()
+                })
+                .collect()
+        }
+        None => neighbors
+            .into_iter()
+            .map(|mut neighs| {
+                neighs.truncate(max_degree);
+                neighs
+            })
+            .collect(),
+    })
+}
+
+#[pyfunction]
+pub fn neighbors_cap_degree_parallel(
+    neighbors_collections: Vec<Vec<Vec<usize>>>,
+    max_degree: usize,
+    distances_collections: Option<Vec<Vec<Vec<f64>>>>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    let n = neighbors_collections.len();
+    let distances_collections: Vec<Option<Vec<Vec<f64>>>> = match distances_collections {
+        Some(distances) => {
+            check_collections(&neighbors_collections, &distances)?;
+            distances.into_iter().map(Some).collect()
+        }
+        None => vec![None; n],
+    };
+    neighbors_collections
+        .into_par_iter()
+        .zip(distances_collections)
+        .map(|(ns, ds)| neighbors_cap_degree(ns, max_degree, ds))
+        .collect()
+}
+
+// Expand the neighbors to k hops, the neighbors of neighbors are 2 hops away
+// The cell itself is not included, the neighbors are ordered by hops
+// The neighbors that are not in the labels are kept but not expanded
+#[pyfunction]
+pub fn neighbors_khop(
+    neighbors: Vec<Vec<usize>>,
+    labels: Vec<usize>,
+    k: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    Ok(neighbors_khop_with_hops(neighbors, labels, k)?.0)
+}
+
+#[pyfunction]
+pub fn neighbors_khop_parallel(
+    neighbors_collections: Vec<Vec<Vec<usize>>>,
+    labels_collections: Vec<Vec<usize>>,
+    k: usize,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&neighbors_collections, &labels_collections)?;
+    neighbors_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ns, ls)| neighbors_khop(ns, ls, k))
+        .collect()
+}
+
+// Same as `neighbors_khop`, the hops of each neighbor is returned
+#[pyfunction]
+pub fn neighbors_khop_with_hops(
+    neighbors: Vec<Vec<usize>>,
+    labels: Vec<usize>,
+    k: usize,
+) -> PyResult<NeighborsHops> {
+    check_same_length("neighbors", neighbors.len(), "labels", labels.len())?;
+    if k == 0 {
+        return Err(PyValueError::new_err("k must be at least 1"));
+    }
+    Ok(khop_neighbors(&neighbors, &labels, k))
+}
+
+#[pyfunction]
+pub fn neighbors_khop_with_hops_parallel(
+    neighbors_collections: Vec<Vec<Vec<usize>>>,
+    labels_collections: Vec<Vec<usize>>,
+    k: usize,
+) -> PyResult<Vec<NeighborsHops>> {
+    check_collections(&neighbors_collections, &labels_collections)?;
+    neighbors_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ns, ls)| neighbors_khop_with_hops(ns, ls, k))
+        .collect()
+}
+
+// Breadth-first search from each cell, stop at k hops
+pub fn khop_neighbors(neighbors: &[Vec<usize>], labels: &[usize], k: usize) -> NeighborsHops {
+    let index = labels_index(labels);
+    labels
+        .iter()
+        .enumerate()
+        .map(|(ix, l)| {
+            let mut visited: HashSet<usize> = HashSet::from([*l]);
+            let mut khop = vec![];
+            let mut hops = vec![];
+            let mut frontier = vec![ix];
+            for hop in 1..=k {
+                let mut next = vec![];
+                for f in frontier {
+                    for n in &neighbors[f] {
+                        if visited.insert(*n) {
+                            khop.push(*n);
+                            hops.push(hop);
+                            if let Some(nix) = index.get(n) {
+                                next.push(*nix);
+                            }
+                        }
+                    }
+                }
+                if next.is_empty() {
+                    break;
+                }
+                frontier = next;
+            }
+            (khop, hops)
+        })
+        .unzip()
+}
+
+// The distances must have the same shape as the neighbors
+fn check_distances(neighbors: &[Vec<usize>], distances: &[Vec<f64>]) -> PyResult<()> {
+    check_same_length("neighbors", neighbors.len(), "distances", distances.len())?;
+    for (ix, (neighs, dists)) in neighbors.iter().zip(distances).enumerate() {
+        if neighs.len() != dists.len() {
+            return Err(PyValueError::new_err(format!(
+                "The cell at index {} has {} neighbors but {} distances",
+                ix,
+                neighs.len(),
+                dists.len()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn labels_index(labels: &[usize]) -> HashMap<usize, usize> {
+    labels.iter().enumerate().map(|(ix, l)| (*l, ix)).collect()
+}
+
+pub fn union_neighbors(neighbors: &[Vec<usize>], labels: &[usize]) -> Vec<Vec<usize>> {
+    let index = labels_index(labels);
+    let mut neighbors_set: Vec<HashSet<usize>> = neighbors
+        .iter()
+        .map(|neighs| neighs.iter().copied().collect())
+        .collect();
+    let mut result = neighbors.to_owned();
+    for (neighs, l) in neighbors.iter().zip(labels) {
+        for n in neighs {
+            if let Some(nix) = index.get(n) {
+                if neighbors_set[*nix].insert(*l) {
+                    result[*nix].push(*l);
+                }
+            }
+        }
+    }
+    result
+}
+
+pub fn mutual_neighbors(neighbors: &[Vec<usize>], labels: &[usize]) -> Vec<Vec<usize>> {
+    let index = labels_index(labels);
+    let neighbors_set: Vec<HashSet<usize>> = neighbors
+        .iter()
+        .map(|neighs| neighs.iter().copied().collect())
+        .collect();
+    neighbors
+        .iter()
+        .zip(labels)
+        .map(|(neighs, l)| {
+            neighs
+                .iter()
+                .filter(|n| match index.get(n) {
+                    Some(nix) => neighbors_set[*nix].contains(l),
+                    None => false,
+                })
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::neighbors_graph::{
+        khop_neighbors, neighbors_cap_degree, neighbors_remove_self, neighbors_symmetric,
+    };
+
+    #[test]
+    fn test_neighbors_symmetric() {
+        let neighbors = vec![vec![10, 11], vec![11, 12], vec![12]];
+        let labels = vec![10, 11, 12];
+        let union = neighbors_symmetric(neighbors.to_owned(), labels.to_owned(), None).unwrap();
+        assert_eq!(union, vec![vec![10, 11], vec![11, 12, 10], vec![12, 11]]);
+        let mutual = neighbors_symmetric(neighbors, labels, Some("intersection")).unwrap();
+        assert_eq!(mutual, vec![vec![10], vec![11], vec![12]]);
+    }
+
+    #[test]
+    fn test_neighbors_remove_self_cap_degree() {
+        let neighbors = vec![vec![0, 1, 2], vec![1, 2, 0]];
+        let no_self = neighbors_remove_self(neighbors.to_owned(), vec![0, 1]).unwrap();
+        assert_eq!(no_self, vec![vec![1, 2], vec![2, 0]]);
+        let capped = neighbors_cap_degree(neighbors.to_owned(), 1, None).unwrap();
+        assert_eq!(capped, vec![vec![0], vec![1]]);
+        let distances = vec![vec![0.0, 2.0, 1.0], vec![0.0, 1.0, 3.0]];
+        let capped = neighbors_cap_degree(neighbors, 2, Some(distances)).unwrap();
+        assert_eq!(capped, vec![vec![0, 2], vec![1, 2]]);
+    }
+
+    #[test]
+    fn test_khop_neighbors() {
+        // A chain 0 - 1 - 2 - 3, with 9 outside of the ROI
+        let neighbors = vec![vec![1], vec![0, 2], vec![1, 3], vec![2, 9]];
+        let labels = vec![0, 1, 2, 3];
+        let (khop, hops) = khop_neighbors(&neighbors, &labels, 2);
+        assert_eq!(khop[0], vec![1, 2]);
+        assert_eq!(hops[0], vec![1, 2]);
+        assert_eq!(khop[3], vec![2, 9, 1]);
+        assert_eq!(hops[3], vec![1, 1, 2]);
+        let (khop, _) = khop_neighbors(&neighbors, &labels, 3);
+        assert_eq!(khop[0], vec![1, 2, 3]);
+    }
+}
@@ -1,15 +1,26 @@
 use std::collections::HashSet;
 
-use delaunator::{triangulate, Point};
+use delaunator::{next_halfedge, prev_halfedge, triangulate, Point, EMPTY};
+use geo::algorithm::bool_ops::BooleanOps;
+use geo::algorithm::intersects::Intersects;
+use geo::{LineString, Polygon};
 use kiddo::distance::squared_euclidean;
 use kiddo::KdTree;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+};
 use rstar::{RTree, RTreeObject, AABB};
 
-use crate::custom_type::{BBox, Point2D, Point3D};
+use crate::custom_type::{BBox, MultiPolygonRings, NeighborsDist, Point2D, Point3D};
+use crate::delaunay_3d::tetrahedralize_edges;
+use crate::geo::{points_bbox, polygon_rings};
+use crate::utils::{check_collections, check_points, check_points_labels, check_same_length, py_kwarg};
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<KDTree2D>()?;
+    m.add_class::<KDTree3D>()?;
     m.add_function(wrap_pyfunction!(points_neighbors_kdtree, m)?)?;
     m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d, m)?)?;
     m.add_function(wrap_pyfunction!(points_neighbors_triangulation, m)?)?;
@@ -18,6 +29,30 @@ pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d_parallel, m)?)?;
     m.add_function(wrap_pyfunction!(points_neighbors_triangulation_parallel, m)?)?;
     m.add_function(wrap_pyfunction!(bbox_neighbors_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_with_dist, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d_with_dist, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_with_dist_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d_with_dist_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_triangulation_3d, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_triangulation_3d_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_triangulation_pruned, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_triangulation_pruned_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_gabriel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_gabriel_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_relative, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_relative_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_voronoi, m)?)?;
+    m.add_function(wrap_pyfunction!(points_voronoi_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_voronoi_neighbors, m)?)?;
+    m.add_function(wrap_pyfunction!(points_voronoi_neighbors_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_bipartite, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d_bipartite, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_bipartite_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d_bipartite_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_adaptive, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d_adaptive, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_adaptive_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(points_neighbors_kdtree_3d_adaptive_parallel, m)?)?;
     Ok(())
 }
 
@@ -27,9 +62,9 @@ pub fn points_neighbors_kdtree(
     labels: Vec<usize>,
     r: f64,
     k: usize,
-) -> Vec<Vec<usize>> {
-    let tree = kdtree_builder(&points, &labels);
-    get_neighbors(tree, points, r, k)
+) -> PyResult<Vec<Vec<usize>>> {
+    let tree = kdtree_builder(&points, &labels)?;
+    Ok(get_neighbors(tree, points, r, k))
 }
 
 #[pyfunction]
@@ -38,7 +73,8 @@ pub fn points_neighbors_kdtree_parallel(
     labels_collections: Vec<Vec<usize>>,
     r: f64,
     k: usize,
-) -> Vec<Vec<Vec<usize>>> {
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
     points_collections
         .into_par_iter()
         .zip(labels_collections)
@@ -53,9 +89,9 @@ pub fn points_neighbors_kdtree_3d(
     labels: Vec<usize>,
     r: f64,
     k: usize,
-) -> Vec<Vec<usize>> {
-    let tree = kdtree_builder(&points, &labels);
-    get_neighbors(tree, points, r, k)
+) -> PyResult<Vec<Vec<usize>>> {
+    let tree = kdtree_builder(&points, &labels)?;
+    Ok(get_neighbors(tree, points, r, k))
 }
 
 #[pyfunction]
@@ -64,7 +100,8 @@ pub fn points_neighbors_kdtree_3d_parallel(
     labels_collections: Vec<Vec<usize>>,
     r: f64,
     k: usize,
-) -> Vec<Vec<Vec<usize>>> {
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
     points_collections
         .into_par_iter()
         .zip(labels_collections)
@@ -73,13 +110,780 @@ pub fn points_neighbors_kdtree_3d_parallel(
         }).collect()
 }
 
+// Same as `points_neighbors_kdtree`, the euclidean distance of each neighbor is returned
+// in the same order as the labels
+#[pyfunction]
+pub fn points_neighbors_kdtree_with_dist(
+    points: Vec<Point2D>,
+    labels: Vec<usize>,
+    r: f64,
+    k: usize,
+) -> PyResult<NeighborsDist> {
+    let tree = kdtree_builder(&points, &labels)?;
+    Ok(get_neighbors_with_dist(tree, points, r, k))
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_with_dist_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    labels_collections: Vec<Vec<usize>>,
+    r: f64,
+    k: usize,
+) -> PyResult<Vec<NeighborsDist>> {
+    check_collections(&points_collections, &labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| {
+            points_neighbors_kdtree_with_dist(ps, ls, r, k)
+        }).collect()
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_3d_with_dist(
+    points: Vec<Point3D>,
+    labels: Vec<usize>,
+    r: f64,
+    k: usize,
+) -> PyResult<NeighborsDist> {
+    let tree = kdtree_builder(&points, &labels)?;
+    Ok(get_neighbors_with_dist(tree, points, r, k))
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_3d_with_dist_parallel(
+    points_collections: Vec<Vec<Point3D>>,
+    labels_collections: Vec<Vec<usize>>,
+    r: f64,
+    k: usize,
+) -> PyResult<Vec<NeighborsDist>> {
+    check_collections(&points_collections, &labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| {
+            points_neighbors_kdtree_3d_with_dist(ps, ls, r, k)
+        }).collect()
+}
+
+// Search the neighbors of `points` among `target_points`,
+// the returned neighbors are the labels of the target points
+#[pyfunction]
+pub fn points_neighbors_kdtree_bipartite(
+    points: Vec<Point2D>,
+    target_points: Vec<Point2D>,
+    target_labels: Vec<usize>,
+    r: f64,
+    k: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_points(&points)?;
+    let tree = kdtree_builder(&target_points, &target_labels)?;
+    Ok(get_neighbors(tree, points, r, k))
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_bipartite_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    target_points_collections: Vec<Vec<Point2D>>,
+    target_labels_collections: Vec<Vec<usize>>,
+    r: f64,
+    k: usize,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &target_points_collections)?;
+    check_collections(&target_points_collections, &target_labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(target_points_collections)
+        .zip(target_labels_collections)
+        .map(|((ps, tps), tls)| {
+            points_neighbors_kdtree_bipartite(ps, tps, tls, r, k)
+        }).collect()
+}
+
 #[pyfunction]
-pub fn points_neighbors_triangulation(points: Vec<Point2D>, labels: Vec<usize>) -> Vec<Vec<usize>> {
-    let points: Vec<Point> = points
+pub fn points_neighbors_kdtree_3d_bipartite(
+    points: Vec<Point3D>,
+    target_points: Vec<Point3D>,
+    target_labels: Vec<usize>,
+    r: f64,
+    k: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_points(&points)?;
+    let tree = kdtree_builder(&target_points, &target_labels)?;
+    Ok(get_neighbors(tree, points, r, k))
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_3d_bipartite_parallel(
+    points_collections: Vec<Vec<Point3D>>,
+    target_points_collections: Vec<Vec<Point3D>>,
+    target_labels_collections: Vec<Vec<usize>>,
+    r: f64,
+    k: usize,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &target_points_collections)?;
+    check_collections(&target_points_collections, &target_labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(target_points_collections)
+        .zip(target_labels_collections)
+        .map(|((ps, tps), tls)| {
+            points_neighbors_kdtree_3d_bipartite(ps, tps, tls, r, k)
+        }).collect()
+}
+
+// Search neighbors with a radius for each point, the radius usually comes from the cell size
+//   either: two points are neighbors if either one reaches the other, d <= max(r_i, r_j)
+//   both: two points are neighbors if both reach each other, d <= min(r_i, r_j)
+#[pyfunction]
+pub fn points_neighbors_kdtree_adaptive(
+    points: Vec<Point2D>,
+    labels: Vec<usize>,
+    radius: Vec<f64>,
+    mode: Option<&str>,
+) -> PyResult<Vec<Vec<usize>>> {
+    adaptive_neighbors(&points, &labels, &radius, mode)
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_adaptive_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    labels_collections: Vec<Vec<usize>>,
+    radius_collections: Vec<Vec<f64>>,
+    mode: Option<&str>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
+    check_collections(&points_collections, &radius_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .zip(radius_collections)
+        .map(|((ps, ls), rs)| {
+            points_neighbors_kdtree_adaptive(ps, ls, rs, mode)
+        }).collect()
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_3d_adaptive(
+    points: Vec<Point3D>,
+    labels: Vec<usize>,
+    radius: Vec<f64>,
+    mode: Option<&str>,
+) -> PyResult<Vec<Vec<usize>>> {
+    adaptive_neighbors(&points, &labels, &radius, mode)
+}
+
+#[pyfunction]
+pub fn points_neighbors_kdtree_3d_adaptive_parallel(
+    points_collections: Vec<Vec<Point3D>>,
+    labels_collections: Vec<Vec<usize>>,
+    radius_collections: Vec<Vec<f64>>,
+    mode: Option<&str>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
+    check_collections(&points_collections, &radius_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .zip(radius_collections)
+        .map(|((ps, ls), rs)| {
+            points_neighbors_kdtree_3d_adaptive(ps, ls, rs, mode)
+        }).collect()
+}
+
+fn adaptive_neighbors<const K: usize>(
+    points: &[[f64; K]],
+    labels: &[usize],
+    radius: &[f64],
+    mode: Option<&str>,
+) -> PyResult<Vec<Vec<usize>>> {
+    let both = match py_kwarg(mode, "either") {
+        "either" => false,
+        "both" => true,
+        mode => {
+            return Err(PyValueError::new_err(format!(
+                "mode must be 'either' or 'both', got '{}'",
+                mode
+            )))
+        }
+    };
+    check_same_length("points", points.len(), "radius", radius.len())?;
+    if let Some(r) = radius.iter().find(|r| !r.is_finite() || (**r <= 0.0)) {
+        return Err(PyValueError::new_err(format!(
+            "radius must be positive numbers, got {}",
+            r
+        )));
+    }
+    check_same_length("points", points.len(), "labels", labels.len())?;
+    let index: Vec<usize> = (0..points.len()).collect();
+    let tree = kdtree_builder(points, &index)?;
+    let neighbors = adaptive_radius_neighbors(&tree, points, radius, both);
+    Ok(neighbors
         .into_iter()
-        .map(|p| Point { x: p[0], y: p[1] })
+        .map(|neighs| neighs.into_iter().map(|j| labels[j]).collect())
+        .collect())
+}
+
+// The tree is built with the index of points, the neighbors of each point
+// are sorted by the distance
+pub fn adaptive_radius_neighbors<const K: usize>(
+    tree: &KdTree<f64, usize, K>,
+    points: &[[f64; K]],
+    radius: &[f64],
+    both: bool,
+) -> Vec<Vec<usize>> {
+    let within: Vec<Vec<(f64, usize)>> = points
+        .par_iter()
+        .zip(radius)
+        .map(|(p, r)| query_point(tree, p, *r, 0))
         .collect();
-    let result = triangulate(&points).triangles;
+    let mut neighbors: Vec<Vec<(f64, usize)>> = vec![vec![]; points.len()];
+    for (i, within) in within.into_iter().enumerate() {
+        for (d, j) in within {
+            let reach_back = d <= radius[j] * radius[j];
+            if reach_back || !both {
+                neighbors[i].push((d, j));
+            }
+            // j won't find i by itself, add it here
+            if !reach_back && !both {
+                neighbors[j].push((d, i));
+            }
+        }
+    }
+    neighbors
+        .into_par_iter()
+        .map(|mut neighs| {
+            neighs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            neighs.into_iter().map(|(_, j)| j).collect()
+        })
+        .collect()
+}
+
+// A kdtree that built once and can be queried for many times,
+// to avoid the cost of rebuilding the tree when sweeping for different `r` or `k`
+#[pyclass]
+pub struct KDTree2D {
+    tree: KdTree<f64, usize, 2>,
+}
+
+#[pymethods]
+impl KDTree2D {
+    #[new]
+    fn new(points: Vec<Point2D>, labels: Vec<usize>) -> PyResult<Self> {
+        Ok(KDTree2D {
+            tree: kdtree_builder(&points, &labels)?,
+        })
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.tree.size()
+    }
+
+    /// Search for all neighbors within `r`
+    fn within(&self, points: Vec<Point2D>, r: f64) -> PyResult<Vec<Vec<usize>>> {
+        check_points(&points)?;
+        Ok(query_neighbors(&self.tree, &points, r, 0))
+    }
+
+    /// Search for `k` nearest neighbors
+    fn knn(&self, points: Vec<Point2D>, k: usize) -> PyResult<Vec<Vec<usize>>> {
+        check_points(&points)?;
+        Ok(query_neighbors(&self.tree, &points, -1.0, k))
+    }
+
+    /// Search for at most `k` nearest neighbors within `r`
+    fn knn_within(&self, points: Vec<Point2D>, r: f64, k: usize) -> PyResult<Vec<Vec<usize>>> {
+        check_points(&points)?;
+        Ok(query_neighbors(&self.tree, &points, r, k))
+    }
+
+    /// Same as the other queries, with the distance of each neighbor returned
+    fn query_with_dist(&self, points: Vec<Point2D>, r: f64, k: usize) -> PyResult<NeighborsDist> {
+        check_points(&points)?;
+        Ok(query_neighbors_with_dist(&self.tree, &points, r, k))
+    }
+}
+
+#[pyclass]
+pub struct KDTree3D {
+    tree: KdTree<f64, usize, 3>,
+}
+
+#[pymethods]
+impl KDTree3D {
+    #[new]
+    fn new(points: Vec<Point3D>, labels: Vec<usize>) -> PyResult<Self> {
+        Ok(KDTree3D {
+            tree: kdtree_builder(&points, &labels)?,
+        })
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.tree.size()
+    }
+
+    /// Search for all neighbors within `r`
+    fn within(&self, points: Vec<Point3D>, r: f64) -> PyResult<Vec<Vec<usize>>> {
+        check_points(&points)?;
+        Ok(query_neighbors(&self.tree, &points, r, 0))
+    }
+
+    /// Search for `k` nearest neighbors
+    fn knn(&self, points: Vec<Point3D>, k: usize) -> PyResult<Vec<Vec<usize>>> {
+        check_points(&points)?;
+        Ok(query_neighbors(&self.tree, &points, -1.0, k))
+    }
+
+    /// Search for at most `k` nearest neighbors within `r`
+    fn knn_within(&self, points: Vec<Point3D>, r: f64, k: usize) -> PyResult<Vec<Vec<usize>>> {
+        check_points(&points)?;
+        Ok(query_neighbors(&self.tree, &points, r, k))
+    }
+
+    /// Same as the other queries, with the distance of each neighbor returned
+    fn query_with_dist(&self, points: Vec<Point3D>, r: f64, k: usize) -> PyResult<NeighborsDist> {
+        check_points(&points)?;
+        Ok(query_neighbors_with_dist(&self.tree, &points, r, k))
+    }
+}
+
+#[pyfunction]
+pub fn points_neighbors_triangulation(
+    points: Vec<Point2D>,
+    labels: Vec<usize>,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_points_labels(&points, &labels)?;
+    Ok(triangulation_neighbors(&points, &labels, None, None, false))
+}
+
+// Delaunay triangulation with long edges removed
+//   max_edge: Remove edges longer than this value
+//   edge_quantile: Remove edges longer than this quantile (0 to 1) of all edges length
+//   remove_hull_slivers: Remove the convex hull edges of sliver triangles
+#[pyfunction]
+pub fn points_neighbors_triangulation_pruned(
+    points: Vec<Point2D>,
+    labels: Vec<usize>,
+    max_edge: Option<f64>,
+    edge_quantile: Option<f64>,
+    remove_hull_slivers: bool,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_points_labels(&points, &labels)?;
+    if let Some(q) = edge_quantile {
+        if !(0.0..=1.0).contains(&q) {
+            return Err(PyValueError::new_err(format!(
+                "edge_quantile must be between 0 and 1, got {}",
+                q
+            )));
+        }
+    }
+    Ok(triangulation_neighbors(&points, &labels, max_edge, edge_quantile, remove_hull_slivers))
+}
+
+#[pyfunction]
+pub fn points_neighbors_triangulation_pruned_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    labels_collections: Vec<Vec<usize>>,
+    max_edge: Option<f64>,
+    edge_quantile: Option<f64>,
+    remove_hull_slivers: bool,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| {
+            points_neighbors_triangulation_pruned(ps, ls, max_edge, edge_quantile, remove_hull_slivers)
+        }).collect()
+}
+
+// The voronoi cell of each point, clipped by the ROI
+// If `roi` is provided, the cells are clipped by the ROI polygon,
+// otherwise clipped by the `bbox`, if both are None, use the bounding box of points.
+// Each cell is a multipolygon, a concave ROI may split a cell into several parts,
+// the cell has no part if it doesn't overlap the ROI, even the point is inside the ROI.
+#[pyfunction]
+pub fn points_voronoi(
+    points: Vec<Point2D>,
+    bbox: Option<BBox>,
+    roi: Option<Vec<Point2D>>,
+) -> PyResult<Vec<MultiPolygonRings>> {
+    if points.is_empty() {
+        return Ok(vec![]);
+    }
+    let (bbox, roi) = voronoi_bounds(&points, bbox, roi)?;
+    let cells = voronoi_cells(&points, bbox);
+    Ok(match roi {
+        Some(roi) => cells
+            .into_par_iter()
+            .map(|cell| clip_by_polygon(cell, &roi))
+            .collect(),
+        None => cells
+            .into_iter()
+            .map(|cell| if cell.is_empty() { vec![] } else { vec![vec![cell]] })
+            .collect(),
+    })
+}
+
+#[pyfunction]
+pub fn points_voronoi_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    bbox_collections: Option<Vec<BBox>>,
+    roi_collections: Option<Vec<Vec<Point2D>>>,
+) -> PyResult<Vec<Vec<MultiPolygonRings>>> {
+    let bounds = voronoi_bounds_collections(&points_collections, bbox_collections, roi_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(bounds)
+        .map(|(ps, (bbox, roi))| {
+            points_voronoi(ps, bbox, roi)
+        }).collect()
+}
+
+// Two points are neighbors if their voronoi cells share a border,
+// the length of the shared border within the ROI is returned,
+// the point itself is included with a border length of 0 as other neighbors search methods
+#[pyfunction]
+pub fn points_voronoi_neighbors(
+    points: Vec<Point2D>,
+    labels: Vec<usize>,
+    bbox: Option<BBox>,
+    roi: Option<Vec<Point2D>>,
+) -> PyResult<NeighborsDist> {
+    check_points_labels(&points, &labels)?;
+    if points.is_empty() {
+        return Ok((vec![], vec![]));
+    }
+    let (bbox, roi) = voronoi_bounds(&points, bbox, roi)?;
+    Ok(voronoi_tagged_cells(&points, bbox)
+        .into_par_iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            let mut borders: Vec<(usize, f64)> = vec![(i, 0.0)];
+            for (k, (start, by)) in cell.iter().enumerate() {
+                if *by == EMPTY {
+                    continue;
+                }
+                let end = cell[(k + 1) % cell.len()].0;
+                let length = match &roi {
+                    Some(roi) => length_inside(start, &end, roi),
+                    None => euclidean_2d(start, &end),
+                };
+                if length > 0.0 {
+                    borders.push((*by, length));
+                }
+            }
+            // A neighbor may cut several edges when the cell is degenerate
+            borders.sort_unstable_by_key(|(j, _)| *j);
+            let mut merged: Vec<(usize, f64)> = vec![];
+            for (j, length) in borders {
+                match merged.last_mut() {
+                    Some(last) if last.0 == j => last.1 += length,
+                    _ => merged.push((j, length)),
+                }
+            }
+            merged.into_iter().map(|(j, length)| (labels[j], length)).unzip()
+        })
+        .unzip())
+}
+
+#[pyfunction]
+pub fn points_voronoi_neighbors_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    labels_collections: Vec<Vec<usize>>,
+    bbox_collections: Option<Vec<BBox>>,
+    roi_collections: Option<Vec<Vec<Point2D>>>,
+) -> PyResult<Vec<NeighborsDist>> {
+    check_collections(&points_collections, &labels_collections)?;
+    let bounds = voronoi_bounds_collections(&points_collections, bbox_collections, roi_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .zip(bounds)
+        .map(|((ps, ls), (bbox, roi))| {
+            points_voronoi_neighbors(ps, ls, bbox, roi)
+        }).collect()
+}
+
+// The bbox to build the voronoi cells and the ROI polygon to clip them
+fn voronoi_bounds(
+    points: &[Point2D],
+    bbox: Option<BBox>,
+    roi: Option<Vec<Point2D>>,
+) -> PyResult<(BBox, Option<Polygon<f64>>)> {
+    check_points(points)?;
+    Ok(match (roi, bbox) {
+        (Some(roi), _) => {
+            check_points(&roi)?;
+            (points_bbox(roi.to_owned())?, Some(Polygon::new(LineString::from(roi), vec![])))
+        }
+        (None, Some(bbox)) => (bbox, None),
+        (None, None) => (points_bbox(points.to_owned())?, None),
+    })
+}
+
+type VoronoiBounds = (Option<BBox>, Option<Vec<Point2D>>);
+
+fn voronoi_bounds_collections(
+    points_collections: &[Vec<Point2D>],
+    bbox_collections: Option<Vec<BBox>>,
+    roi_collections: Option<Vec<Vec<Point2D>>>,
+) -> PyResult<Vec<VoronoiBounds>> {
+    let n = points_collections.len();
+    let bbox_collections: Vec<Option<BBox>> = match bbox_collections {
+        Some(bbox) => {
+            check_collections(points_collections, &bbox)?;
+            bbox.into_iter().map(Some).collect()
+        }
+        None => vec![None; n],
+    };
+    let roi_collections: Vec<Option<Vec<Point2D>>> = match roi_collections {
+        Some(roi) => {
+            check_collections(points_collections, &roi)?;
+            roi.into_iter().map(Some).collect()
+        }
+        None => vec![None; n],
+    };
+    Ok(bbox_collections.into_iter().zip(roi_collections).collect())
+}
+
+// The voronoi cell as a closed ring, empty if the cell is outside the bbox
+pub fn voronoi_cells(points: &[Point2D], bbox: BBox) -> Vec<Vec<Point2D>> {
+    voronoi_tagged_cells(points, bbox)
+        .into_iter()
+        .map(|cell| {
+            let mut ring: Vec<Point2D> = cell.into_iter().map(|(p, _)| p).collect();
+            // Close the ring
+            if let Some(first) = ring.first() {
+                ring.push(*first);
+            }
+            ring
+        })
+        .collect()
+}
+
+// The voronoi cell is the intersection of half-planes between the point and its
+// delaunay neighbors, start from the bbox and cut by each half-plane.
+// Each vertex is tagged with the point whose half-plane makes the edge to the next vertex,
+// EMPTY for the edges of the bbox.
+fn voronoi_tagged_cells(points: &[Point2D], bbox: BBox) -> Vec<Vec<(Point2D, usize)>> {
+    let n = points.len();
+    let mut adjacency: Vec<Vec<usize>> = vec![vec![]; n];
+    for (p1, p2, _) in triangulation_edges(points, false) {
+        adjacency[p1].push(p2);
+        adjacency[p2].push(p1);
+    }
+    let rect = vec![
+        ([bbox.0, bbox.1], EMPTY),
+        ([bbox.2, bbox.1], EMPTY),
+        ([bbox.2, bbox.3], EMPTY),
+        ([bbox.0, bbox.3], EMPTY),
+    ];
+    (0..n)
+        .into_par_iter()
+        .map(|i| {
+            let p = points[i];
+            // Duplicated or collinear points are not triangulated, compare with all the points
+            let others: Vec<usize> = if adjacency[i].is_empty() {
+                (0..n).collect()
+            } else {
+                adjacency[i].to_owned()
+            };
+            let mut cell = rect.to_owned();
+            for j in others {
+                if points[j] == p {
+                    continue;
+                }
+                cell = clip_half_plane(&cell, &p, &points[j], j);
+                if cell.is_empty() {
+                    break;
+                }
+            }
+            cell
+        })
+        .collect()
+}
+
+// Sutherland-Hodgman clipping, keep the part of polygon that is closer to `a` than `b`,
+// the new edge on the bisector is tagged with `tag`
+fn clip_half_plane(
+    polygon: &[(Point2D, usize)],
+    a: &Point2D,
+    b: &Point2D,
+    tag: usize,
+) -> Vec<(Point2D, usize)> {
+    let mid = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+    let normal = [b[0] - a[0], b[1] - a[1]];
+    // Negative if on the side of `a`
+    let side = |p: &Point2D| (p[0] - mid[0]) * normal[0] + (p[1] - mid[1]) * normal[1];
+
+    let mut result = Vec::with_capacity(polygon.len() + 1);
+    for (ix, (current, edge)) in polygon.iter().enumerate() {
+        let next = &polygon[(ix + 1) % polygon.len()].0;
+        let (s1, s2) = (side(current), side(next));
+        if s1 < 0.0 {
+            result.push((*current, *edge));
+        } else if s1 == 0.0 {
+            // The edge leaves the kept side along the bisector
+            result.push((*current, if s2 > 0.0 { tag } else { *edge }));
+        }
+        if (s1 < 0.0) & (s2 > 0.0) || (s1 > 0.0) & (s2 < 0.0) {
+            let t = s1 / (s1 - s2);
+            let cut = [
+                current[0] + t * (next[0] - current[0]),
+                current[1] + t * (next[1] - current[1]),
+            ];
+            result.push((cut, if s1 < 0.0 { tag } else { *edge }));
+        }
+    }
+    result
+}
+
+fn clip_by_polygon(cell: Vec<Point2D>, roi: &Polygon<f64>) -> MultiPolygonRings {
+    if cell.is_empty() {
+        return vec![];
+    }
+    let cell = Polygon::new(LineString::from(cell), vec![]);
+    cell.intersection(roi).iter().map(polygon_rings).collect()
+}
+
+// The length of the segment inside the polygon, the segment is split at where
+// it crosses the polygon border, and each piece is tested by its middle point
+fn length_inside(a: &Point2D, b: &Point2D, polygon: &Polygon<f64>) -> f64 {
+    let r = [b[0] - a[0], b[1] - a[1]];
+    let rr = r[0] * r[0] + r[1] * r[1];
+    if rr == 0.0 {
+        return 0.0;
+    }
+    let cross = |u: [f64; 2], v: [f64; 2]| u[0] * v[1] - u[1] * v[0];
+    let mut breaks = vec![0.0, 1.0];
+    for line in std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .flat_map(|ring| ring.lines())
+    {
+        let c = [line.start.x - a[0], line.start.y - a[1]];
+        let d = [line.end.x - a[0], line.end.y - a[1]];
+        let s = [d[0] - c[0], d[1] - c[1]];
+        let denom = cross(r, s);
+        if denom != 0.0 {
+            let (t, u) = (cross(c, s) / denom, cross(c, r) / denom);
+            if (0.0..=1.0).contains(&t) & (0.0..=1.0).contains(&u) {
+                breaks.push(t);
+            }
+        } else if cross(c, r) == 0.0 {
+            // Collinear, the ends of the border line split the segment
+            for e in [c, d] {
+                let t = (e[0] * r[0] + e[1] * r[1]) / rr;
+                if (0.0..=1.0).contains(&t) {
+                    breaks.push(t);
+                }
+            }
+        }
+    }
+    breaks.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    let inside: f64 = breaks
+        .windows(2)
+        .filter(|w| w[1] > w[0])
+        .filter(|w| {
+            let t = (w[0] + w[1]) / 2.0;
+            polygon.intersects(&geo::Point::new(a[0] + t * r[0], a[1] + t * r[1]))
+        })
+        .map(|w| w[1] - w[0])
+        .sum();
+    inside * rr.sqrt()
+}
+
+#[pyfunction]
+pub fn points_neighbors_gabriel(points: Vec<Point2D>, labels: Vec<usize>) -> PyResult<Vec<Vec<usize>>> {
+    check_points_labels(&points, &labels)?;
+    let edges = gabriel_edges(&points);
+    Ok(edges_neighbors(&labels, edges.into_iter().map(|(p1, p2, _)| (p1, p2))))
+}
+
+#[pyfunction]
+pub fn points_neighbors_gabriel_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    labels_collections: Vec<Vec<usize>>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| {
+            points_neighbors_gabriel(ps, ls)
+        }).collect()
+}
+
+#[pyfunction]
+pub fn points_neighbors_relative(points: Vec<Point2D>, labels: Vec<usize>) -> PyResult<Vec<Vec<usize>>> {
+    check_points_labels(&points, &labels)?;
+    let edges = relative_neighborhood_edges(&points)?;
+    Ok(edges_neighbors(&labels, edges.into_iter().map(|(p1, p2, _)| (p1, p2))))
+}
+
+#[pyfunction]
+pub fn points_neighbors_relative_parallel(
+    points_collections: Vec<Vec<Point2D>>,
+    labels_collections: Vec<Vec<usize>>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| {
+            points_neighbors_relative(ps, ls)
+        }).collect()
+}
+
+// Neighbors from 3D delaunay tetrahedralization
+#[pyfunction]
+pub fn points_neighbors_triangulation_3d(
+    points: Vec<Point3D>,
+    labels: Vec<usize>,
+) -> PyResult<Vec<Vec<usize>>> {
+    check_points_labels(&points, &labels)?;
+    Ok(edges_neighbors(&labels, tetrahedralize_edges(&points)?.into_iter()))
+}
+
+#[pyfunction]
+pub fn points_neighbors_triangulation_3d_parallel(
+    points_collections: Vec<Vec<Point3D>>,
+    labels_collections: Vec<Vec<usize>>,
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
+    points_collections
+        .into_par_iter()
+        .zip(labels_collections)
+        .map(|(ps, ls)| {
+            points_neighbors_triangulation_3d(ps, ls)
+        }).collect()
+}
+
+pub fn triangulation_neighbors(
+    points: &[Point2D],
+    labels: &[usize],
+    max_edge: Option<f64>,
+    edge_quantile: Option<f64>,
+    remove_hull_slivers: bool,
+) -> Vec<Vec<usize>> {
+    let mut edges = triangulation_edges(points, remove_hull_slivers);
+
+    let mut threshold = max_edge.unwrap_or(f64::INFINITY);
+    if let Some(q) = edge_quantile {
+        threshold = threshold.min(edges_length_quantile(&edges, q));
+    }
+    edges.retain(|(_, _, d)| *d <= threshold);
+
+    edges_neighbors(labels, edges.into_iter().map(|(p1, p2, _)| (p1, p2)))
+}
+
+// Convert the undirected edges of point index to neighbors of labels
+pub fn edges_neighbors(
+    labels: &[usize],
+    edges: impl Iterator<Item = (usize, usize)>,
+) -> Vec<Vec<usize>> {
     let mut neighbors: Vec<HashSet<usize>> = labels
         .iter()
         .map(|i| {
@@ -90,14 +894,10 @@ pub fn points_neighbors_triangulation(points: Vec<Point2D>, labels: Vec<usize>)
         })
         .collect();
 
-    (0..result.len()).into_iter().step_by(3).for_each(|i| {
-        let slice = vec![result[i], result[i + 1], result[i + 2]];
-        for p1 in &slice {
-            for p2 in &slice {
-                neighbors[*p1].insert(labels[*p2]);
-            }
-        }
-    });
+    for (p1, p2) in edges {
+        neighbors[p1].insert(labels[p2]);
+        neighbors[p2].insert(labels[p1]);
+    }
 
     neighbors
         .into_iter()
@@ -105,11 +905,121 @@ pub fn points_neighbors_triangulation(points: Vec<Point2D>, labels: Vec<usize>)
         .collect()
 }
 
+// Gabriel graph, an edge is kept if no other points inside the circle whose diameter is the edge,
+// it's a subgraph of delaunay triangulation, so only the opposite vertices of the edge are checked
+pub fn gabriel_edges(points: &[Point2D]) -> Vec<(usize, usize, f64)> {
+    let tri_points: Vec<Point> = points
+        .iter()
+        .map(|p| Point { x: p[0], y: p[1] })
+        .collect();
+    let tri = triangulate(&tri_points);
+    // The point is inside the circle if the angle at the point is larger than 90 degree
+    let inside = |e: usize| {
+        let p1 = &points[tri.triangles[e]];
+        let p2 = &points[tri.triangles[next_halfedge(e)]];
+        let v = &points[tri.triangles[prev_halfedge(e)]];
+        (p1[0] - v[0]) * (p2[0] - v[0]) + (p1[1] - v[1]) * (p2[1] - v[1]) < 0.0
+    };
+
+    let mut edges = vec![];
+    for e in 0..tri.triangles.len() {
+        let opposite = tri.halfedges[e];
+        if (opposite != EMPTY) & (opposite < e) {
+            continue;
+        }
+        if inside(e) || ((opposite != EMPTY) && inside(opposite)) {
+            continue;
+        }
+        let p1 = tri.triangles[e];
+        let p2 = tri.triangles[next_halfedge(e)];
+        edges.push((p1, p2, euclidean_2d(&points[p1], &points[p2])));
+    }
+    edges
+}
+
+// Relative neighborhood graph, an edge is kept if there is no other point that
+// closer to both ends than the length of the edge, it's a subgraph of gabriel graph
+pub fn relative_neighborhood_edges(points: &[Point2D]) -> PyResult<Vec<(usize, usize, f64)>> {
+    let index: Vec<usize> = (0..points.len()).collect();
+    let tree = kdtree_builder(points, &index)?;
+    Ok(gabriel_edges(points)
+        .into_iter()
+        .filter(|(p1, p2, d)| {
+            let within = tree
+                .within_unsorted(&points[*p1], d * d, &squared_euclidean)
+                .unwrap();
+            // The lune is open, the points on its border don't break the edge
+            !within.into_iter().any(|(d1, r)| {
+                (*r != *p1)
+                    & (*r != *p2)
+                    & (d1 < d * d)
+                    & (euclidean_2d(&points[*r], &points[*p2]) < *d)
+            })
+        })
+        .collect())
+}
+
+// The triangle on the hull is considered as a sliver
+// if the angle opposite to the hull edge is larger than this
+const HULL_SLIVER_ANGLE: f64 = 2.0 * std::f64::consts::FRAC_PI_3;
+
+// Get the unique edges of delaunay triangulation, (point index, point index, edge length)
+pub fn triangulation_edges(points: &[Point2D], remove_hull_slivers: bool) -> Vec<(usize, usize, f64)> {
+    let tri_points: Vec<Point> = points
+        .iter()
+        .map(|p| Point { x: p[0], y: p[1] })
+        .collect();
+    let tri = triangulate(&tri_points);
+
+    let mut edges = vec![];
+    for e in 0..tri.triangles.len() {
+        let opposite = tri.halfedges[e];
+        // Each inner edge is shared by two half-edges, only keep one of them
+        if (opposite != EMPTY) & (opposite < e) {
+            continue;
+        }
+        let p1 = tri.triangles[e];
+        let p2 = tri.triangles[next_halfedge(e)];
+        if (opposite == EMPTY) & remove_hull_slivers {
+            let p3 = tri.triangles[prev_halfedge(e)];
+            if vertex_angle(&points[p3], &points[p1], &points[p2]) > HULL_SLIVER_ANGLE {
+                continue;
+            }
+        }
+        edges.push((p1, p2, euclidean_2d(&points[p1], &points[p2])));
+    }
+    edges
+}
+
+fn edges_length_quantile(edges: &[(usize, usize, f64)], q: f64) -> f64 {
+    if edges.is_empty() {
+        return f64::INFINITY;
+    }
+    let mut length: Vec<f64> = edges.iter().map(|(_, _, d)| *d).collect();
+    length.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let ix = (q.clamp(0.0, 1.0) * (length.len() - 1) as f64).round() as usize;
+    length[ix]
+}
+
+fn euclidean_2d(p1: &Point2D, p2: &Point2D) -> f64 {
+    ((p1[0] - p2[0]).powi(2) + (p1[1] - p2[1]).powi(2)).sqrt()
+}
+
+// The angle at vertex `v` formed by `v -> p1` and `v -> p2`
+fn vertex_angle(v: &Point2D, p1: &Point2D, p2: &Point2D) -> f64 {
+    let a = [p1[0] - v[0], p1[1] - v[1]];
+    let b = [p2[0] - v[0], p2[1] - v[1]];
+    let cross = a[0] * b[1] - a[1] * b[0];
+    let dot = a[0] * b[0] + a[1] * b[1];
+    cross.abs().atan2(dot)
+}
+
 #[pyfunction]
 pub fn points_neighbors_triangulation_parallel(
     points_collections: Vec<Vec<Point2D>>,
     labels_collections: Vec<Vec<usize>>,
-) -> Vec<Vec<Vec<usize>>> {
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&points_collections, &labels_collections)?;
     points_collections
         .into_par_iter()
         .zip(labels_collections)
@@ -125,8 +1035,9 @@ pub fn bbox_neighbors(
     labels: Vec<usize>,
     expand: f64,
     scale: f64,
-) -> Vec<Vec<usize>> {
-    bbox_neighbors_rtree(init_bbox(bbox, labels), expand, scale)
+) -> PyResult<Vec<Vec<usize>>> {
+    check_same_length("bbox", bbox.len(), "labels", labels.len())?;
+    Ok(bbox_neighbors_rtree(init_bbox(bbox, labels), expand, scale))
 }
 
 
@@ -137,7 +1048,8 @@ pub fn bbox_neighbors_parallel(
     labels_collections: Vec<Vec<usize>>,
     expand: f64,
     scale: f64,
-) -> Vec<Vec<Vec<usize>>> {
+) -> PyResult<Vec<Vec<Vec<usize>>>> {
+    check_collections(&bbox_collections, &labels_collections)?;
     bbox_collections
         .into_par_iter()
         .zip(labels_collections)
@@ -148,14 +1060,20 @@ pub fn bbox_neighbors_parallel(
 
 // Build a kdtree using kiddo with labels
 pub fn kdtree_builder<const K: usize>(
-    points: &Vec<[f64; K]>,
-    labels: &Vec<usize>,
-) -> KdTree<f64, usize, K> {
+    points: &[[f64; K]],
+    labels: &[usize],
+) -> PyResult<KdTree<f64, usize, K>> {
+    check_same_length("points", points.len(), "labels", labels.len())?;
     let mut tree: KdTree<f64, usize, K> = KdTree::new();
     for (p, label) in points.iter().zip(labels) {
-        tree.add(p, *label).unwrap();
+        tree.add(p, *label).map_err(|_| {
+            PyValueError::new_err(format!(
+                "Failed to add point {:?} (label {}) to the kdtree, coordinates must be finite",
+                p, label
+            ))
+        })?;
     }
-    tree
+    Ok(tree)
 }
 
 pub fn get_neighbors<const K: usize>(
@@ -164,30 +1082,92 @@ pub fn get_neighbors<const K: usize>(
     r: f64,
     k: usize,
 ) -> Vec<Vec<usize>> {
-    let neighbors = points
+    points
         .iter()
         .map(|p| {
-            if r > 0.0 {
-                if k > 0 {
-                    let within = tree.within(p, r * r, &squared_euclidean).unwrap();
-                    let mut neighbors = vec![];
-                    for (ix, (_, i)) in within.iter().enumerate() {
-                        if ix < k {
-                            neighbors.push(**i)
-                        }
-                    }
-                    neighbors
-                } else {
-                    let within = tree.within_unsorted(p, r * r, &squared_euclidean).unwrap();
-                    within.iter().map(|(_, i)| **i).collect()
-                }
-            } else {
-                let within = tree.nearest(p, k, &squared_euclidean).unwrap();
-                within.iter().map(|(_, i)| **i).collect()
-            }
+            query_point(&tree, p, r, k)
+                .into_iter()
+                .map(|(_, i)| i)
+                .collect()
         })
-        .collect();
-    neighbors
+        .collect()
+}
+
+// The distance is the euclidean distance, not the squared one used in the tree
+pub fn get_neighbors_with_dist<const K: usize>(
+    tree: KdTree<f64, usize, K>,
+    points: Vec<[f64; K]>,
+    r: f64,
+    k: usize,
+) -> NeighborsDist {
+    points
+        .iter()
+        .map(|p| {
+            query_point(&tree, p, r, k)
+                .into_iter()
+                .map(|(d, i)| (i, d.sqrt()))
+                .unzip()
+        })
+        .unzip()
+}
+
+// Query a prebuilt tree with arbitrary points in parallel
+pub fn query_neighbors<const K: usize>(
+    tree: &KdTree<f64, usize, K>,
+    points: &[[f64; K]],
+    r: f64,
+    k: usize,
+) -> Vec<Vec<usize>> {
+    points
+        .par_iter()
+        .map(|p| {
+            query_point(tree, p, r, k)
+                .into_iter()
+                .map(|(_, i)| i)
+                .collect()
+        })
+        .collect()
+}
+
+pub fn query_neighbors_with_dist<const K: usize>(
+    tree: &KdTree<f64, usize, K>,
+    points: &[[f64; K]],
+    r: f64,
+    k: usize,
+) -> NeighborsDist {
+    points
+        .par_iter()
+        .map(|p| {
+            query_point(tree, p, r, k)
+                .into_iter()
+                .map(|(d, i)| (i, d.sqrt()))
+                .unzip()
+        })
+        .unzip()
+}
+
+// Search the neighbors of a point, return (squared distance, label) pairs
+//   r > 0 & k > 0: at most k nearest neighbors within r
+//   r > 0: all neighbors within r
+//   otherwise: k nearest neighbors
+pub fn query_point<const K: usize>(
+    tree: &KdTree<f64, usize, K>,
+    p: &[f64; K],
+    r: f64,
+    k: usize,
+) -> Vec<(f64, usize)> {
+    let within = if r > 0.0 {
+        if k > 0 {
+            let mut within = tree.within(p, r * r, &squared_euclidean).unwrap();
+            within.truncate(k);
+            within
+        } else {
+            tree.within_unsorted(p, r * r, &squared_euclidean).unwrap()
+        }
+    } else {
+        tree.nearest(p, k, &squared_euclidean).unwrap()
+    };
+    within.into_iter().map(|(d, i)| (d, *i)).collect()
 }
 
 // fn points_neighbors_within(tree: &KdTree<f64, usize, 2>, point: &(f64, f64), r: f64)
@@ -232,7 +1212,7 @@ pub struct BBox2D {
     miny: f64,
     maxx: f64,
     maxy: f64,
-    label: usize,
+    pub(crate) label: usize,
 }
 
 impl BBox2D {
@@ -294,3 +1274,130 @@ fn scale_bbox(bbox: &Vec<BBox2D>, scale: f64) -> Vec<AABB<[f64; 2]>> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::geo::polygon_area;
+    use crate::neighbors_search::{
+        adaptive_radius_neighbors, gabriel_edges, kdtree_builder, points_voronoi,
+        points_voronoi_neighbors, relative_neighborhood_edges, triangulation_neighbors,
+        voronoi_cells,
+    };
+
+    #[test]
+    fn test_gabriel_relative() {
+        // A triangle with an obtuse angle at point 2
+        let points = vec![[0.0, 0.0], [4.0, 0.0], [2.0, 1.0], [2.0, 5.0]];
+        let gabriel: Vec<(usize, usize)> = gabriel_edges(&points)
+            .into_iter()
+            .map(|(p1, p2, _)| (p1.min(p2), p1.max(p2)))
+            .collect();
+        assert!(!gabriel.contains(&(0, 1)));
+        assert!(gabriel.contains(&(0, 2)));
+
+        // Point 2 is closer to both 0 and 3 than they are to each other
+        let relative: Vec<(usize, usize)> = relative_neighborhood_edges(&points)
+            .unwrap()
+            .into_iter()
+            .map(|(p1, p2, _)| (p1.min(p2), p1.max(p2)))
+            .collect();
+        assert!(!relative.contains(&(0, 3)));
+        assert!(relative.contains(&(2, 3)));
+        assert!(relative.len() <= gabriel.len());
+
+        // Points 2 and 3 are on the border of the lune of 0-1, the edge is kept
+        let points = vec![[0.0, 0.0], [5.0, 0.0], [4.0, 3.0], [1.0, 3.0]];
+        let relative: Vec<(usize, usize)> = relative_neighborhood_edges(&points)
+            .unwrap()
+            .into_iter()
+            .map(|(p1, p2, _)| (p1.min(p2), p1.max(p2)))
+            .collect();
+        assert!(relative.contains(&(0, 1)));
+    }
+
+    #[test]
+    fn test_voronoi_cells() {
+        let points = vec![[0.0, 0.0], [2.0, 0.0], [0.0, 2.0], [2.0, 2.0], [1.0, 1.0]];
+        let cells = voronoi_cells(&points, (0.0, 0.0, 2.0, 2.0));
+        let area: Vec<f64> = cells.into_iter().map(polygon_area).collect();
+        assert!((area[4] - 2.0).abs() < 1e-9);
+        assert!((area.iter().sum::<f64>() - 4.0).abs() < 1e-9);
+
+        let labels = vec![0, 1, 2, 3, 4];
+        let (neighbors, borders) =
+            points_voronoi_neighbors(points.to_owned(), labels.to_owned(), None, None).unwrap();
+        assert_eq!(neighbors[4], vec![0, 1, 2, 3, 4]);
+        assert_eq!(neighbors[0], vec![0, 4]);
+        assert!(borders[4][..4].iter().all(|b| (b - 2f64.sqrt()).abs() < 1e-9));
+
+        let roi = vec![[0.0, 0.0], [2.0, 0.0], [0.0, 2.0], [0.0, 0.0]];
+        let cells = points_voronoi(points.to_owned(), None, Some(roi.to_owned())).unwrap();
+        assert!(cells[3].is_empty());
+        let area: f64 = cells
+            .into_iter()
+            .flatten()
+            .map(|rings| polygon_area(rings[0].to_owned()))
+            .sum();
+        assert!((area - 2.0).abs() < 1e-9);
+        let (neighbors, borders) = points_voronoi_neighbors(points, labels, None, Some(roi)).unwrap();
+        assert_eq!(neighbors[4], vec![0, 1, 2, 4]);
+        assert!((borders[4][0] - 2f64.sqrt()).abs() < 1e-9);
+        assert!((borders[4][1] - 2f64.sqrt() / 2.0).abs() < 1e-9);
+
+        // The cell of the point in the notch is split into the two arms of the ROI
+        let points = vec![[2.0, 0.5], [2.0, 3.0]];
+        let roi = vec![
+            [0.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 4.0],
+            [3.0, 4.0],
+            [3.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 4.0],
+            [0.0, 4.0],
+            [0.0, 0.0],
+        ];
+        let cells = points_voronoi(points.to_owned(), None, Some(roi.to_owned())).unwrap();
+        assert_eq!(cells[1].len(), 2);
+        for part in &cells[1] {
+            assert!((polygon_area(part[0].to_owned()) - 2.25).abs() < 1e-9);
+        }
+        let (neighbors, borders) = points_voronoi_neighbors(points, vec![0, 1], None, Some(roi)).unwrap();
+        assert_eq!(neighbors[0], vec![0, 1]);
+        assert!((borders[0][1] - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_triangulation_pruned() {
+        let points = vec![[0.0, 0.0], [10.0, 0.0], [5.0, 1.0], [5.0, 5.0]];
+        let labels = vec![0, 1, 2, 3];
+
+        let neighbors = triangulation_neighbors(&points, &labels, None, None, false);
+        assert!(neighbors[0].contains(&1));
+
+        let neighbors = triangulation_neighbors(&points, &labels, None, None, true);
+        assert!(!neighbors[0].contains(&1));
+        assert!(neighbors[0].contains(&2));
+
+        let neighbors = triangulation_neighbors(&points, &labels, Some(6.0), None, false);
+        assert!(!neighbors[0].contains(&1));
+        assert!(neighbors[3].contains(&2));
+
+        let neighbors = triangulation_neighbors(&points, &labels, None, Some(0.0), false);
+        // Only the shortest edge 2-3 is kept
+        assert_eq!(neighbors.iter().map(|n| n.len()).sum::<usize>(), 6);
+    }
+
+    #[test]
+    fn test_adaptive_radius() {
+        // A large cell at 0 and two small cells
+        let points = vec![[0.0, 0.0, 0.0], [3.0, 0.0, 0.0], [3.5, 0.0, 0.0]];
+        let radius = vec![4.0, 1.0, 1.0];
+        let tree = kdtree_builder(&points, &[0, 1, 2]).unwrap();
+
+        let either = adaptive_radius_neighbors(&tree, &points, &radius, false);
+        assert_eq!(either, vec![vec![0, 1, 2], vec![1, 2, 0], vec![2, 1, 0]]);
+        let both = adaptive_radius_neighbors(&tree, &points, &radius, true);
+        assert_eq!(both, vec![vec![0], vec![1, 2], vec![2, 1]]);
+    }
+}
@@ -0,0 +1,274 @@
+// Robust geometric predicates in 3D
+//
+// The determinant is evaluated in floating point first, it's trusted when it's larger than
+// the error bound, otherwise it's recomputed exactly with the expansion arithmetic of
+// Shewchuk (1997), so the sign is always correct. An expansion is a sum of non-overlapping
+// floats in increasing order of magnitude, the sign is the sign of the last one.
+use crate::custom_type::Point3D;
+
+const EPSILON: f64 = f64::EPSILON / 2.0;
+const O3D_ERRBOUND: f64 = (7.0 + 56.0 * EPSILON) * EPSILON;
+const ISP_ERRBOUND: f64 = (16.0 + 224.0 * EPSILON) * EPSILON;
+
+type Expansion = Vec<f64>;
+
+// Positive if `d` is on the side of (a, b, c) that they look counter-clockwise from,
+// zero if the four points are coplanar.
+// The magnitude is six times the volume of the tetrahedron when the sign is certain.
+pub fn orient3d(a: &Point3D, b: &Point3D, c: &Point3D, d: &Point3D) -> f64 {
+    let (u, v, w) = (sub(b, a), sub(c, a), sub(d, a));
+    let det = det3(&u, &v, &w);
+    let permanent = det3_permanent(&abs(&u), &abs(&v), &abs(&w));
+    if det.abs() > O3D_ERRBOUND * permanent {
+        return det;
+    }
+    let (u, v, w) = (sub_exact(b, a), sub_exact(c, a), sub_exact(d, a));
+    sign_of(&det3_exact(&u, &v, &w))
+}
+
+// Negative if `e` is inside the circumsphere of (a, b, c, d), which is positive
+// oriented by `orient3d`, zero if the five points are cospherical.
+pub fn insphere(a: &Point3D, b: &Point3D, c: &Point3D, d: &Point3D, e: &Point3D) -> f64 {
+    let (ta, tb, tc, td) = (sub(a, e), sub(b, e), sub(c, e), sub(d, e));
+    let lifts = [dot(&ta, &ta), dot(&tb, &tb), dot(&tc, &tc), dot(&td, &td)];
+    let det = (lifts[3] * det3(&ta, &tb, &tc) - lifts[2] * det3(&ta, &tb, &td))
+        + (lifts[1] * det3(&ta, &tc, &td) - lifts[0] * det3(&tb, &tc, &td));
+    let (pa, pb, pc, pd) = (abs(&ta), abs(&tb), abs(&tc), abs(&td));
+    let permanent = (lifts[3] * det3_permanent(&pa, &pb, &pc)
+        + lifts[2] * det3_permanent(&pa, &pb, &pd))
+        + (lifts[1] * det3_permanent(&pa, &pc, &pd) + lifts[0] * det3_permanent(&pb, &pc, &pd));
+    if det.abs() > ISP_ERRBOUND * permanent {
+        return det;
+    }
+
+    let (ta, tb, tc, td) = (
+        sub_exact(a, e),
+        sub_exact(b, e),
+        sub_exact(c, e),
+        sub_exact(d, e),
+    );
+    let lift = |t: &[Expansion; 3]| {
+        expansion_sum(
+            &expansion_sum(&expansion_mul(&t[0], &t[0]), &expansion_mul(&t[1], &t[1])),
+            &expansion_mul(&t[2], &t[2]),
+        )
+    };
+    let terms = [
+        expansion_mul(&lift(&td), &det3_exact(&ta, &tb, &tc)),
+        negate(&expansion_mul(&lift(&tc), &det3_exact(&ta, &tb, &td))),
+        expansion_mul(&lift(&tb), &det3_exact(&ta, &tc, &td)),
+        negate(&expansion_mul(&lift(&ta), &det3_exact(&tb, &tc, &td))),
+    ];
+    sign_of(&terms.iter().fold(vec![], |acc, t| expansion_sum(&acc, t)))
+}
+
+fn sub(a: &Point3D, b: &Point3D) -> Point3D {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: &Point3D, b: &Point3D) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn abs(a: &Point3D) -> Point3D {
+    [a[0].abs(), a[1].abs(), a[2].abs()]
+}
+
+// a . (b x c)
+fn det3(a: &Point3D, b: &Point3D, c: &Point3D) -> f64 {
+    a[0] * (b[1] * c[2] - b[2] * c[1])
+        + a[1] * (b[2] * c[0] - b[0] * c[2])
+        + a[2] * (b[0] * c[1] - b[1] * c[0])
+}
+
+// The determinant with all the terms added as absolute values, of non-negative inputs
+fn det3_permanent(a: &Point3D, b: &Point3D, c: &Point3D) -> f64 {
+    a[0] * (b[1] * c[2] + b[2] * c[1])
+        + a[1] * (b[2] * c[0] + b[0] * c[2])
+        + a[2] * (b[0] * c[1] + b[1] * c[0])
+}
+
+fn sub_exact(a: &Point3D, b: &Point3D) -> [Expansion; 3] {
+    [0, 1, 2].map(|i| {
+        let (x, y) = two_sum(a[i], -b[i]);
+        compress(vec![y, x])
+    })
+}
+
+fn det3_exact(a: &[Expansion; 3], b: &[Expansion; 3], c: &[Expansion; 3]) -> Expansion {
+    let minor = |i: usize, j: usize| {
+        expansion_sum(
+            &expansion_mul(&b[i], &c[j]),
+            &negate(&expansion_mul(&b[j], &c[i])),
+        )
+    };
+    let terms = [
+        expansion_mul(&a[0], &minor(1, 2)),
+        expansion_mul(&a[1], &minor(2, 0)),
+        expansion_mul(&a[2], &minor(0, 1)),
+    ];
+    terms.iter().fold(vec![], |acc, t| expansion_sum(&acc, t))
+}
+
+fn sign_of(e: &[f64]) -> f64 {
+    e.last().copied().unwrap_or(0.0)
+}
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let bv = x - a;
+    let av = x - bv;
+    (x, (a - av) + (b - bv))
+}
+
+fn fast_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    (x, b - (x - a))
+}
+
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    (x, a.mul_add(b, -x))
+}
+
+fn compress(e: Expansion) -> Expansion {
+    e.into_iter().filter(|x| *x != 0.0).collect()
+}
+
+fn negate(e: &[f64]) -> Expansion {
+    e.iter().map(|x| -x).collect()
+}
+
+// Merge the components by magnitude and accumulate them with error-free sums
+fn expansion_sum(e: &[f64], f: &[f64]) -> Expansion {
+    let mut merged = Vec::with_capacity(e.len() + f.len());
+    let (mut i, mut j) = (0, 0);
+    while (i < e.len()) || (j < f.len()) {
+        if (j == f.len()) || ((i < e.len()) && (e[i].abs() < f[j].abs())) {
+            merged.push(e[i]);
+            i += 1;
+        } else {
+            merged.push(f[j]);
+            j += 1;
+        }
+    }
+    if merged.is_empty() {
+        return merged;
+    }
+    let mut h = Vec::with_capacity(merged.len());
+    let mut q = merged[0];
+    for g in &merged[1..] {
+        let (sum, err) = two_sum(q, *g);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = sum;
+    }
+    if q != 0.0 {
+        h.push(q);
+    }
+    h
+}
+
+fn scale_expansion(e: &[f64], b: f64) -> Expansion {
+    let mut h = Vec::with_capacity(2 * e.len());
+    let (mut q, err) = two_product(e[0], b);
+    if err != 0.0 {
+        h.push(err);
+    }
+    for x in &e[1..] {
+        let (hi, lo) = two_product(*x, b);
+        let (sum, err) = two_sum(q, lo);
+        if err != 0.0 {
+            h.push(err);
+        }
+        let (sum, err) = fast_two_sum(hi, sum);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = sum;
+    }
+    if q != 0.0 {
+        h.push(q);
+    }
+    h
+}
+
+fn expansion_mul(e: &[f64], f: &[f64]) -> Expansion {
+    if e.is_empty() {
+        return vec![];
+    }
+    f.iter().fold(vec![], |acc, x| {
+        expansion_sum(&acc, &scale_expansion(e, *x))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::predicates::{insphere, orient3d};
+
+    // The exact determinants of integer coordinates
+    fn orient3d_i128(p: &[[i128; 3]; 4]) -> i128 {
+        let d = |i: usize| [0, 1, 2].map(|k| p[i][k] - p[0][k]);
+        let (u, v, w) = (d(1), d(2), d(3));
+        u[0] * (v[1] * w[2] - v[2] * w[1])
+            + u[1] * (v[2] * w[0] - v[0] * w[2])
+            + u[2] * (v[0] * w[1] - v[1] * w[0])
+    }
+
+    fn sign(x: f64) -> i128 {
+        if x > 0.0 {
+            1
+        } else if x < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    #[test]
+    fn test_orient3d() {
+        // Large coordinates so that the products are not exact in floating point,
+        // the fourth point is on the plane or moved off by the smallest step
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..2000 {
+            let mut p = [[0i128; 3]; 4];
+            for q in p.iter_mut().take(3) {
+                *q = [0, 1, 2].map(|_| rng.gen_range(0..(1i128 << 26)));
+            }
+            let (s, t) = (rng.gen_range(-3..4), rng.gen_range(-3..4));
+            p[3] = [0, 1, 2].map(|k| p[0][k] + s * (p[1][k] - p[0][k]) + t * (p[2][k] - p[0][k]));
+            p[3][rng.gen_range(0..3)] += rng.gen_range(-1..2);
+            let f = p.map(|q| q.map(|x| x as f64));
+            assert_eq!(
+                sign(orient3d(&f[0], &f[1], &f[2], &f[3])),
+                orient3d_i128(&p).signum()
+            );
+        }
+    }
+
+    #[test]
+    fn test_insphere() {
+        // Points on a sphere of radius 5 at a large offset
+        let offset = (1u64 << 40) as f64;
+        let on_sphere = [
+            [3.0, 4.0, 0.0],
+            [0.0, -3.0, 4.0],
+            [-5.0, 0.0, 0.0],
+            [0.0, 0.0, -5.0],
+        ];
+        let [a, b, c, d] = on_sphere.map(|p| p.map(|x| x + offset));
+        let (a, b) = if orient3d(&a, &b, &c, &d) > 0.0 {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let at = |p: [f64; 3]| p.map(|x| x + offset);
+        assert_eq!(insphere(&a, &b, &c, &d, &at([4.0, 0.0, 3.0])), 0.0);
+        assert!(insphere(&a, &b, &c, &d, &at([4.0, 0.0, 2.999])) < 0.0);
+        assert!(insphere(&a, &b, &c, &d, &at([4.0, 0.0, 3.001])) > 0.0);
+    }
+}
@@ -1,7 +1,683 @@
-// opencv ccl algorithm, 4 connectivity
-// return area, centroid, eccentricity, border coords
-// fn mask2cell() {}
+// Convert the segmentation label mask to cells
+//
+// The pixel at (row, col) is the point (x = col, y = row), a pixel covers
+// the unit square centered at the point, so the outline lies on the pixel corners.
+use std::collections::{HashMap, HashSet};
 
-// convex_hull or concave_hull to determine the cell shape
-// return borders
-// fn cell_border() {}
+use ndarray::{s, Array2, Array3, ArrayView2, Axis};
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArray3, PyReadonlyArray2, PyReadonlyArray3};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::custom_type::{BBox, MultiPolygonRings, Point2D, PolygonRings};
+use crate::simplify::simplify_dp;
+use crate::utils::{check_not_empty, check_points, check_polygon, check_same_length, py_kwarg};
+
+// (labels, area, centroid, bbox, eccentricity, border)
+type CellTable = (
+    Vec<i64>,
+    Vec<usize>,
+    Vec<Point2D>,
+    Vec<BBox>,
+    Vec<f64>,
+    Vec<MultiPolygonRings>,
+);
+
+// (label, statistics, the first pixel of each 4-connected component)
+type MaskCell = (i64, CellStats, Vec<(usize, usize)>);
+
+type IntensityArrays<'py> = (
+    &'py PyArray1<i64>,
+    &'py PyArray2<f64>,
+    &'py PyArray2<f64>,
+    &'py PyArray2<f64>,
+    &'py PyArray2<f64>,
+    &'py PyArray3<f64>,
+);
+
+pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(mask2cell, m)?)?;
+    m.add_function(wrap_pyfunction!(cell_intensity, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_rasterize, m)?)?;
+    m.add_function(wrap_pyfunction!(mask_contours, m)?)?;
+    Ok(())
+}
+
+// Every label except the background 0 is a cell, the cells are sorted by label.
+// The bbox is (minx, miny, maxx, maxy) of pixel centers, the eccentricity comes from
+// the second moments of pixels, the border is a multipolygon with a part from `cell_border`
+// for each 4-connected component of the label, so a label split into pieces is still one cell.
+#[pyfunction]
+pub fn mask2cell(py: Python, mask: PyReadonlyArray2<i64>) -> PyResult<CellTable> {
+    let mask = mask.as_array();
+    Ok(py.allow_threads(|| {
+        let cells = mask_cells(mask);
+        let measures: Vec<(f64, Point2D, f64, MultiPolygonRings)> = cells
+            .par_iter()
+            .map(|(label, s, starts)| {
+                let n = s.area as f64;
+                let (cx, cy) = (s.sx / n, s.sy / n);
+                let mu20 = s.sxx / n - cx * cx;
+                let mu02 = s.syy / n - cy * cy;
+                let mu11 = s.sxy / n - cx * cy;
+                let half_trace = (mu20 + mu02) / 2.0;
+                let delta = (((mu20 - mu02) / 2.0).powi(2) + mu11.powi(2)).sqrt();
+                let (l1, l2) = (half_trace + delta, (half_trace - delta).max(0.0));
+                let eccentricity = if l1 > 0.0 { (1.0 - l2 / l1).sqrt() } else { 0.0 };
+                let border = starts
+                    .iter()
+                    .map(|start| vec![cell_border(mask, *label, *start, false)])
+                    .collect();
+                (n, [cx, cy], eccentricity, border)
+            })
+            .collect();
+
+        let mut table: CellTable = (vec![], vec![], vec![], vec![], vec![], vec![]);
+        for ((label, s, _), (_, centroid, eccentricity, border)) in cells.into_iter().zip(measures) {
+            table.0.push(label);
+            table.1.push(s.area);
+            table.2.push(centroid);
+            table.3.push(s.bbox);
+            table.4.push(eccentricity);
+            table.5.push(border);
+        }
+        table
+    }))
+}
+
+// The outline of each label in the mask as a multipolygon, in the same order as `mask2cell`,
+// a part for each 4-connected component of the label, see `cell_border` for the tracing.
+// The outline can be simplified by the Douglas-Peucker algorithm with `tolerance`,
+// the simplified outline doesn't self-intersect.
+#[pyfunction]
+pub fn mask_contours(
+    py: Python,
+    mask: PyReadonlyArray2<i64>,
+    subpixel: bool,
+    tolerance: Option<f64>,
+) -> PyResult<(Vec<i64>, Vec<MultiPolygonRings>)> {
+    if let Some(t) = tolerance {
+        if !t.is_finite() || (t < 0.0) {
+            return Err(PyValueError::new_err(format!(
+                "tolerance must be a non-negative number, got {}",
+                t
+            )));
+        }
+    }
+    let mask = mask.as_array();
+    Ok(py.allow_threads(|| {
+        let cells = mask_cells(mask);
+        let contours: Vec<MultiPolygonRings> = cells
+            .par_iter()
+            .map(|(label, _, starts)| {
+                starts
+                    .iter()
+                    .map(|start| {
+                        let ring = cell_border(mask, *label, *start, subpixel);
+                        match tolerance {
+                            Some(t) => vec![simplify_dp(&ring, t)],
+                            None => vec![ring],
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        (cells.into_iter().map(|(l, _, _)| l).collect(), contours)
+    }))
+}
+
+// The accumulated statistics of the pixels of a cell
+struct CellStats {
+    area: usize,
+    sx: f64,
+    sy: f64,
+    sxx: f64,
+    syy: f64,
+    sxy: f64,
+    bbox: BBox,
+}
+
+impl CellStats {
+    fn new() -> CellStats {
+        CellStats {
+            area: 0,
+            sx: 0.0,
+            sy: 0.0,
+            sxx: 0.0,
+            syy: 0.0,
+            sxy: 0.0,
+            bbox: (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    fn add(&mut self, r: usize, c: usize) {
+        let (x, y) = (c as f64, r as f64);
+        self.area += 1;
+        self.sx += x;
+        self.sy += y;
+        self.sxx += x * x;
+        self.syy += y * y;
+        self.sxy += x * y;
+        self.bbox = (
+            self.bbox.0.min(x),
+            self.bbox.1.min(y),
+            self.bbox.2.max(x),
+            self.bbox.3.max(y),
+        );
+    }
+}
+
+// A pixel is on the border if any of its 4-connected neighbors
+// has a different label or is outside the mask
+fn on_border(mask: ArrayView2<i64>, r: usize, c: usize) -> bool {
+    let (nrows, ncols) = mask.dim();
+    let label = mask[[r, c]];
+    (r == 0)
+        || (c == 0)
+        || (r + 1 == nrows)
+        || (c + 1 == ncols)
+        || (mask[[r - 1, c]] != label)
+        || (mask[[r + 1, c]] != label)
+        || (mask[[r, c - 1]] != label)
+        || (mask[[r, c + 1]] != label)
+}
+
+// The cells of the mask sorted by label, the same cells as `label_pixels`, with the first pixel
+// in raster order of each 4-connected component of the label, the labels are processed in parallel
+fn mask_cells(mask: ArrayView2<i64>) -> Vec<MaskCell> {
+    label_pixels(mask, false)
+        .into_par_iter()
+        .map(|(label, pixels)| {
+            let mut stats = CellStats::new();
+            for (r, c) in &pixels {
+                stats.add(*r, *c);
+            }
+            let starts = component_starts(mask, label, &pixels);
+            (label, stats, starts)
+        })
+        .collect()
+}
+
+// Flood fill the 4-connected components of a label, the components are sorted by
+// their first pixel in raster order
+fn component_starts(
+    mask: ArrayView2<i64>,
+    label: i64,
+    pixels: &[(usize, usize)],
+) -> Vec<(usize, usize)> {
+    let mut visited: HashSet<(usize, usize)> = HashSet::with_capacity(pixels.len());
+    let mut starts = vec![];
+    for p in pixels {
+        if !visited.insert(*p) {
+            continue;
+        }
+        let mut start = *p;
+        let mut stack = vec![*p];
+        while let Some((r, c)) = stack.pop() {
+            start = start.min((r, c));
+            // The index out of the mask wraps to a large number and is not found
+            for q in [(r.wrapping_sub(1), c), (r + 1, c), (r, c.wrapping_sub(1)), (r, c + 1)] {
+                if (mask.get(q) == Some(&label)) && visited.insert(q) {
+                    stack.push(q);
+                }
+            }
+        }
+        starts.push(start);
+    }
+    starts.sort_unstable();
+    starts
+}
+
+// Trace the outer boundary of a 4-connected component of a label along the pixel edges,
+// starting from the top edge of `start`, the first pixel of the component in raster order,
+// and keeping the label on the right hand side. The holes inside the component are not traced.
+// With `subpixel`, the vertices are the midpoints of the pixel edges, which is the same as
+// the marching squares at level 0.5 between the label and the others, otherwise the vertices
+// are the pixel corners, and the polygon covers exactly the pixels.
+// The returned ring is closed and the collinear vertices are removed.
+pub fn cell_border(
+    mask: ArrayView2<i64>,
+    label: i64,
+    start: (usize, usize),
+    subpixel: bool,
+) -> Vec<Point2D> {
+    let (nrows, ncols) = mask.dim();
+    let inside = |r: isize, c: isize| {
+        (r >= 0)
+            && (c >= 0)
+            && (r < nrows as isize)
+            && (c < ncols as isize)
+            && (mask[[r as usize, c as usize]] == label)
+    };
+    // The (row, col) step of east, south, west and north, in the clockwise order on the image
+    const STEPS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+    // The pixels ahead on the left and right, relative to the corner at the top-left of pixel
+    const AHEAD: [[(isize, isize); 2]; 4] = [
+        [(-1, 0), (0, 0)],
+        [(0, 0), (0, -1)],
+        [(0, -1), (-1, -1)],
+        [(-1, -1), (-1, 0)],
+    ];
+
+    let start = (start.0 as isize, start.1 as isize);
+    let (mut corner, mut dir) = (start, 0);
+    let mut ring: Vec<Point2D> = vec![];
+    loop {
+        let next = (corner.0 + STEPS[dir].0, corner.1 + STEPS[dir].1);
+        if subpixel {
+            ring.push([
+                (corner.1 + next.1) as f64 / 2.0 - 0.5,
+                (corner.0 + next.0) as f64 / 2.0 - 0.5,
+            ]);
+        }
+        corner = next;
+        let [left, right] = AHEAD[dir];
+        let new_dir = if !inside(corner.0 + right.0, corner.1 + right.1) {
+            (dir + 1) % 4
+        } else if inside(corner.0 + left.0, corner.1 + left.1) {
+            (dir + 3) % 4
+        } else {
+            dir
+        };
+        if !subpixel && (new_dir != dir) {
+            ring.push([corner.1 as f64 - 0.5, corner.0 as f64 - 0.5]);
+        }
+        dir = new_dir;
+        if (corner == start) && (dir == 0) {
+            break;
+        }
+    }
+    if subpixel {
+        ring = remove_collinear(ring);
+    } else {
+        // The start corner is pushed last when turning back to east
+        ring.rotate_right(1);
+    }
+    ring.push(ring[0]);
+    ring
+}
+
+fn remove_collinear(ring: Vec<Point2D>) -> Vec<Point2D> {
+    let n = ring.len();
+    (0..n)
+        .filter(|i| {
+            let (a, b, c) = (ring[(i + n - 1) % n], ring[*i], ring[(i + 1) % n]);
+            (b[0] - a[0]) * (c[1] - b[1]) != (b[1] - a[1]) * (c[0] - b[0])
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+// The intensity of each cell in each channel of an image in shape of (channels, rows, cols),
+// return (labels, mean, median, sum, max, percentiles), the statistics are in shape of
+// (cells, channels) and the percentiles are in shape of (percentiles, cells, channels).
+// The cells are the labels in the same order as `mask2cell`, a label split into pieces is one cell.
+// The percentiles use linear interpolation as numpy. If `exclude_border`, the border
+// pixels of cells are not counted, a cell without pixels left gets NaN.
+#[pyfunction]
+pub fn cell_intensity<'py>(
+    py: Python<'py>,
+    mask: PyReadonlyArray2<i64>,
+    image: PyReadonlyArray3<f64>,
+    percentiles: Vec<f64>,
+    exclude_border: bool,
+) -> PyResult<IntensityArrays<'py>> {
+    let mask = mask.as_array();
+    let image = image.as_array();
+    let (nchannels, nrows, ncols) = image.dim();
+    if (nrows, ncols) != mask.dim() {
+        return Err(PyValueError::new_err(format!(
+            "The shape of image ({}, {}) does not match the shape of mask {:?}",
+            nrows,
+            ncols,
+            mask.dim()
+        )));
+    }
+    if let Some(q) = percentiles.iter().find(|q| !(0.0..=100.0).contains(*q)) {
+        return Err(PyValueError::new_err(format!(
+            "percentiles must be in [0, 100], got {}",
+            q
+        )));
+    }
+
+    let (labels, rows) = py.allow_threads(|| {
+        let cells = label_pixels(mask, exclude_border);
+        let rows: Vec<Vec<f64>> = cells
+            .par_iter()
+            .map(|(_, pixels)| {
+                (0..nchannels)
+                    .flat_map(|ch| {
+                        let mut values: Vec<f64> =
+                            pixels.iter().map(|(r, c)| image[[ch, *r, *c]]).collect();
+                        values.sort_unstable_by(|a, b| a.total_cmp(b));
+                        channel_stats(&values, &percentiles)
+                    })
+                    .collect()
+            })
+            .collect();
+        let labels: Vec<i64> = cells.into_iter().map(|(l, _)| l).collect();
+        (labels, rows)
+    });
+
+    // Each row is [mean, median, sum, max, percentiles..] repeated for channels
+    let nstats = 4 + percentiles.len();
+    let ncells = labels.len();
+    let mut stats = Array3::<f64>::zeros((nstats, ncells, nchannels));
+    for (i, row) in rows.into_iter().enumerate() {
+        for (j, v) in row.into_iter().enumerate() {
+            stats[[j % nstats, i, j / nstats]] = v;
+        }
+    }
+    let channel_array = |k: usize| stats.index_axis(Axis(0), k).to_owned().into_pyarray(py);
+    Ok((
+        labels.into_pyarray(py),
+        channel_array(0),
+        channel_array(1),
+        channel_array(2),
+        channel_array(3),
+        stats
+            .slice(s![4.., .., ..])
+            .to_owned()
+            .into_pyarray(py),
+    ))
+}
+
+// The pixels of each label sorted by label, the labels whose pixels are all
+// excluded are kept with no pixels
+fn label_pixels(mask: ArrayView2<i64>, exclude_border: bool) -> Vec<(i64, Vec<(usize, usize)>)> {
+    let (nrows, ncols) = mask.dim();
+    let cells = (0..nrows)
+        .into_par_iter()
+        .fold(HashMap::new, |mut cells: HashMap<i64, Vec<(usize, usize)>>, r| {
+            for c in 0..ncols {
+                let label = mask[[r, c]];
+                if label == 0 {
+                    continue;
+                }
+                let pixels = cells.entry(label).or_default();
+                if !(exclude_border && on_border(mask, r, c)) {
+                    pixels.push((r, c));
+                }
+            }
+            cells
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (label, pixels) in b {
+                a.entry(label).or_default().extend(pixels);
+            }
+            a
+        });
+    let mut cells: Vec<(i64, Vec<(usize, usize)>)> = cells.into_iter().collect();
+    cells.sort_unstable_by_key(|(l, _)| *l);
+    cells
+}
+
+// [mean, median, sum, max, percentiles..] of the sorted values
+fn channel_stats(sorted: &[f64], percentiles: &[f64]) -> Vec<f64> {
+    let n = sorted.len();
+    if n == 0 {
+        return vec![f64::NAN; 4 + percentiles.len()];
+    }
+    let sum: f64 = sorted.iter().sum();
+    let mut stats = vec![
+        sum / n as f64,
+        percentile(sorted, 50.0),
+        sum,
+        sorted[n - 1],
+    ];
+    stats.extend(percentiles.iter().map(|q| percentile(sorted, *q)));
+    stats
+}
+
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q / 100.0 * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+// Render polygons with holes into a label mask of `shape` (rows, cols), the rings of all
+// the parts of a multipolygon can be given as one polygon since the parts don't overlap,
+// the pixel (row, col) is at (origin.x + col * resolution, origin.y + row * resolution),
+// a pixel gets the label if the pixel is inside the polygon by the even-odd rule.
+// The overlapped pixels take the label of the last polygon in "last" mode,
+// or raise an error in "error" mode. The rows are rendered in parallel.
+#[pyfunction]
+pub fn polygons_rasterize<'py>(
+    py: Python<'py>,
+    polygons: Vec<PolygonRings>,
+    labels: Vec<i64>,
+    shape: (usize, usize),
+    origin: Point2D,
+    resolution: f64,
+    overlap: Option<&str>,
+) -> PyResult<&'py PyArray2<i64>> {
+    check_same_length("polygons", polygons.len(), "labels", labels.len())?;
+    let allow_overlap = match py_kwarg(overlap, "last") {
+        "last" => true,
+        "error" => false,
+        overlap => {
+            return Err(PyValueError::new_err(format!(
+                "overlap must be 'last' or 'error', got '{}'",
+                overlap
+            )))
+        }
+    };
+    if !resolution.is_finite() || (resolution <= 0.0) {
+        return Err(PyValueError::new_err(format!(
+            "resolution must be a positive number, got {}",
+            resolution
+        )));
+    }
+    check_points(&[origin])?;
+    if labels.contains(&0) {
+        return Err(PyValueError::new_err(
+            "Labels must not be 0, which is the background",
+        ));
+    }
+    for rings in &polygons {
+        check_not_empty("polygon", rings.len())?;
+        for ring in rings {
+            check_polygon(ring)?;
+        }
+    }
+    // Transform to the pixel coordinates
+    let polygons: Vec<PolygonRings> = polygons
+        .into_iter()
+        .map(|rings| {
+            rings
+                .into_iter()
+                .map(|ring| {
+                    ring.into_iter()
+                        .map(|[x, y]| [(x - origin[0]) / resolution, (y - origin[1]) / resolution])
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+    let mask = py.allow_threads(|| rasterize(&polygons, &labels, shape, allow_overlap))?;
+    Ok(mask.into_pyarray(py))
+}
+
+// The polygons are in pixel coordinates
+pub fn rasterize(
+    polygons: &[PolygonRings],
+    labels: &[i64],
+    shape: (usize, usize),
+    allow_overlap: bool,
+) -> PyResult<Array2<i64>> {
+    let (nrows, ncols) = shape;
+    // The polygons that cover each row, in the input order
+    let mut row_polygons: Vec<Vec<usize>> = vec![vec![]; nrows];
+    for (i, rings) in polygons.iter().enumerate() {
+        let (miny, maxy) = rings
+            .iter()
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+                (lo.min(p[1]), hi.max(p[1]))
+            });
+        let r0 = miny.ceil().max(0.0) as usize;
+        let r1 = (maxy.floor() + 1.0).clamp(0.0, nrows as f64) as usize;
+        for row in row_polygons.iter_mut().take(r1).skip(r0) {
+            row.push(i);
+        }
+    }
+
+    let mut mask = Array2::<i64>::zeros(shape);
+    mask.axis_iter_mut(Axis(0))
+        .into_par_iter()
+        .zip(row_polygons)
+        .enumerate()
+        .try_for_each(|(r, (mut row, covered))| {
+            let y = r as f64;
+            for i in covered {
+                for [x0, x1] in scanline_spans(&polygons[i], y) {
+                    let c0 = x0.ceil().max(0.0) as usize;
+                    // The pixel exactly at the right end is outside
+                    let c1 = x1.ceil().clamp(0.0, ncols as f64) as usize;
+                    for c in c0..c1 {
+                        if !allow_overlap && (row[c] != 0) && (row[c] != labels[i]) {
+                            return Err(PyValueError::new_err(format!(
+                                "The polygons of label {} and {} overlap at pixel ({}, {})",
+                                row[c], labels[i], r, c
+                            )));
+                        }
+                        row[c] = labels[i];
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    Ok(mask)
+}
+
+// The inside spans of a horizontal line with the polygon by the even-odd rule,
+// the edges are half open in y so that a vertex on the line is counted once
+fn scanline_spans(rings: &[Vec<Point2D>], y: f64) -> Vec<[f64; 2]> {
+    let mut xs: Vec<f64> = rings
+        .iter()
+        .flat_map(|ring| {
+            let n = ring.len();
+            (0..n).filter_map(move |i| {
+                let (a, b) = (ring[i], ring[(i + 1) % n]);
+                if (a[1] > y) != (b[1] > y) {
+                    Some(a[0] + (y - a[1]) / (b[1] - a[1]) * (b[0] - a[0]))
+                } else {
+                    None
+                }
+            })
+        })
+        .collect();
+    xs.sort_unstable_by(|a, b| a.total_cmp(b));
+    xs.chunks_exact(2).map(|s| [s[0], s[1]]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use crate::preprocessing::{
+        cell_border, channel_stats, label_pixels, mask_cells, rasterize,
+    };
+
+    #[test]
+    fn test_mask_cells() {
+        let mask = array![[0, 1, 1, 0], [0, 1, 1, 2], [3, 0, 0, 2]];
+        let cells = mask_cells(mask.view());
+        assert_eq!(cells.len(), 3);
+        let (label, cell, starts) = &cells[0];
+        assert_eq!(*label, 1);
+        assert_eq!(cell.area, 4);
+        assert_eq!(cell.bbox, (1.0, 0.0, 2.0, 1.0));
+        assert_eq!((cell.sx / 4.0, cell.sy / 4.0), (1.5, 0.5));
+        assert_eq!(*starts, vec![(0, 1)]);
+        assert_eq!(cells[1].1.area, 2);
+        assert_eq!(cells[2].2, vec![(2, 0)]);
+
+        // A label in two pieces, one touches the other only by a corner, is one cell
+        let mask = array![[0, 0, 0, 1], [1, 1, 1, 0], [1, 0, 0, 0]];
+        let cells = mask_cells(mask.view());
+        assert_eq!(cells.len(), 1);
+        let (_, cell, starts) = &cells[0];
+        assert_eq!(cell.area, 5);
+        assert_eq!(cell.bbox, (0.0, 0.0, 3.0, 2.0));
+        assert_eq!(*starts, vec![(0, 3), (1, 0)]);
+        // The outlines of the pieces cover exactly the pixels of the label
+        let rings: Vec<Vec<[f64; 2]>> = starts
+            .iter()
+            .map(|start| cell_border(mask.view(), 1, *start, false))
+            .collect();
+        let rendered = rasterize(&[rings], &[1], (3, 4), true).unwrap();
+        assert_eq!(rendered, mask);
+    }
+
+    #[test]
+    fn test_channel_stats() {
+        let mask = array![[1, 1, 1], [1, 1, 1], [1, 1, 1]];
+        let cells = label_pixels(mask.view(), true);
+        assert_eq!(cells, vec![(1, vec![(1, 1)])]);
+
+        // A label split into pieces is one cell, the rows match the cells of `mask_cells`
+        let mask = array![[0, 0, 0, 1], [1, 1, 1, 0], [1, 0, 2, 2]];
+        let labels: Vec<i64> = label_pixels(mask.view(), true)
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect();
+        let cells: Vec<i64> = mask_cells(mask.view()).into_iter().map(|(l, _, _)| l).collect();
+        assert_eq!(labels, vec![1, 2]);
+        assert_eq!(labels, cells);
+
+        let stats = channel_stats(&[1.0, 2.0, 3.0, 10.0], &[25.0]);
+        assert_eq!(stats, vec![4.0, 2.5, 16.0, 10.0, 1.75]);
+        assert!(channel_stats(&[], &[]).iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let square = vec![vec![[0.5, 0.5], [2.5, 0.5], [2.5, 2.5], [0.5, 2.5]]];
+        let donut = vec![
+            vec![[-0.5, -0.5], [3.5, -0.5], [3.5, 3.5], [-0.5, 3.5]],
+            vec![[0.5, 0.5], [2.5, 0.5], [2.5, 2.5], [0.5, 2.5]],
+        ];
+        let mask = rasterize(&[donut.clone(), square.clone()], &[1, 2], (4, 5), true).unwrap();
+        assert_eq!(
+            mask,
+            array![
+                [1, 1, 1, 1, 0],
+                [1, 2, 2, 1, 0],
+                [1, 2, 2, 1, 0],
+                [1, 1, 1, 1, 0]
+            ]
+        );
+        // The donut doesn't overlap the square, but a filled square does
+        assert!(rasterize(&[donut, square.clone()], &[1, 2], (4, 4), false).is_ok());
+        let big = vec![vec![[0.0, 0.0], [3.0, 0.0], [3.0, 3.0], [0.0, 3.0]]];
+        assert!(rasterize(&[big, square], &[1, 2], (4, 4), false).is_err());
+    }
+
+    #[test]
+    fn test_cell_border() {
+        // An L shape with a pixel connected by corner only
+        let mask = array![[0, 0, 0, 1], [1, 1, 1, 0], [1, 0, 0, 0]];
+        let ring = cell_border(mask.view(), 1, (1, 0), false);
+        assert_eq!(
+            ring,
+            vec![
+                [-0.5, 0.5],
+                [2.5, 0.5],
+                [2.5, 1.5],
+                [0.5, 1.5],
+                [0.5, 2.5],
+                [-0.5, 2.5],
+                [-0.5, 0.5]
+            ]
+        );
+        let polygon: Vec<Vec<[f64; 2]>> = vec![ring];
+        let rendered = rasterize(&[polygon], &[1], (3, 4), true).unwrap();
+        assert_eq!(rendered, array![[0, 0, 0, 0], [1, 1, 1, 0], [1, 0, 0, 0]]);
+
+        // The edge midpoints of a single pixel is a diamond
+        let ring = cell_border(mask.view(), 1, (0, 3), true);
+        assert_eq!(ring, vec![[3.0, -0.5], [3.5, 0.0], [3.0, 0.5], [2.5, 0.0], [3.0, -0.5]]);
+    }
+}
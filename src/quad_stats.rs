@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
 use crate::custom_type::{BBox, Point2D};
 use crate::geo::points_bbox;
+use crate::utils::check_points;
 
 pub struct QuadStats {
     pub(crate) nx: usize,
@@ -24,9 +28,10 @@ impl QuadStats {
         bbox: Option<BBox>,
         quad: Option<(usize, usize)>,
         rect_side: Option<(f64, f64)>,
-    ) -> HashMap<usize, usize>
+    ) -> PyResult<HashMap<usize, usize>>
     {
-        let points_bbox = points_bbox(points.to_owned());
+        check_points(&points)?;
+        let points_bbox = points_bbox(points.to_owned())?;
         let bbox = match bbox {
             Some(data) => {
                 if (data.0 <= points_bbox.0)
@@ -56,10 +61,19 @@ impl QuadStats {
                 // if quad is None, match rect_side
                 match rect_side {
                     Some(rect) => {
+                        if !((rect.0 > 0.0) & (rect.1 > 0.0)) {
+                            return Err(PyValueError::new_err(format!(
+                                "The side of the rect must be positive, got {:?}",
+                                rect
+                            )));
+                        }
                         let nx = (width / rect.0).floor() as usize;
                         let ny = (height / rect.1).floor() as usize;
                         if (nx == 0) | (ny == 0) {
-                            panic!("The side of the rect is bigger than the bbox")
+                            return Err(PyValueError::new_err(format!(
+                                "The side of the rect {:?} is bigger than the bbox {:?}",
+                                rect, bbox
+                            )));
                         } else {
                             self.nx = nx;
                             self.ny = ny;
@@ -75,7 +89,10 @@ impl QuadStats {
         }
 
         if (self.nx == 0) | (self.ny == 0) {
-            panic!("quadratic cannot perform with 0 rectangles")
+            return Err(PyValueError::new_err(format!(
+                "quadratic cannot perform with 0 rectangles, got quad ({}, {})",
+                self.nx, self.ny
+            )));
         }
 
         let nx_f: f64 = self.nx as f64;
@@ -109,7 +126,7 @@ impl QuadStats {
             self.cells_grid_id.push(id_);
         }
 
-        dict_id_count
+        Ok(dict_id_count)
     }
 }
 
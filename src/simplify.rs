@@ -0,0 +1,291 @@
+// Simplification and smoothing of polygon outlines
+//
+// The polygons are single rings, the results are closed rings that never self-intersect,
+// the changes that break the ring are rolled back, so a valid input is always a valid output.
+use geo::algorithm::chaikin_smoothing::ChaikinSmoothing;
+use geo::algorithm::intersects::Intersects;
+use geo::algorithm::simplifyvw::SimplifyVWPreserve;
+use geo::{Line, LineString, Polygon};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use rstar::primitives::{GeomWithData, Line as SegmentLine};
+use rstar::{PointDistance, RTree, RTreeObject};
+
+use crate::custom_type::Point2D;
+use crate::utils::{check_polygon, py_kwarg};
+
+type IndexedSegment = GeomWithData<SegmentLine<Point2D>, usize>;
+
+pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(polygons_simplify, m)?)?;
+    m.add_function(wrap_pyfunction!(polygons_smooth, m)?)?;
+    Ok(())
+}
+
+// Simplify the polygons in parallel
+// "dp": Douglas-Peucker, `tolerance` is the maximum distance from the removed vertices
+//       to the simplified border
+// "vw": Visvalingam-Whyatt, `tolerance` is the minimum area of the triangle formed by
+//       a vertex and its neighbors to keep the vertex
+#[pyfunction]
+pub fn polygons_simplify(
+    polygons: Vec<Vec<Point2D>>,
+    tolerance: f64,
+    method: Option<&str>,
+) -> PyResult<Vec<Vec<Point2D>>> {
+    if !tolerance.is_finite() || (tolerance < 0.0) {
+        return Err(PyValueError::new_err(format!(
+            "tolerance must be a non-negative number, got {}",
+            tolerance
+        )));
+    }
+    let simplify: fn(&[Point2D], f64) -> Vec<Point2D> = match py_kwarg(method, "dp") {
+        "dp" => simplify_dp,
+        "vw" => simplify_vw,
+        method => {
+            return Err(PyValueError::new_err(format!(
+                "method must be 'dp' or 'vw', got '{}'",
+                method
+            )))
+        }
+    };
+    for p in &polygons {
+        check_polygon(p)?;
+    }
+    Ok(polygons
+        .par_iter()
+        .map(|p| simplify(p, tolerance))
+        .collect())
+}
+
+// The iterations of Chaikin's corner cutting, 10 iterations make 1024 times the vertices
+const MAX_SMOOTH_ITERATIONS: usize = 10;
+
+// Chaikin's corner cutting in parallel, each iteration doubles the number of vertices,
+// the iterations stop early if the ring would self-intersect
+#[pyfunction]
+pub fn polygons_smooth(
+    polygons: Vec<Vec<Point2D>>,
+    iterations: usize,
+) -> PyResult<Vec<Vec<Point2D>>> {
+    if iterations > MAX_SMOOTH_ITERATIONS {
+        return Err(PyValueError::new_err(format!(
+            "iterations must be at most {}, got {}",
+            MAX_SMOOTH_ITERATIONS, iterations
+        )));
+    }
+    for p in &polygons {
+        check_polygon(p)?;
+    }
+    Ok(polygons
+        .par_iter()
+        .map(|p| smooth_chaikin(p, iterations))
+        .collect())
+}
+
+// Douglas-Peucker on a closed ring, the ring is split at the first vertex and
+// the vertex farthest from it. When a simplified edge crosses another edge,
+// the farthest removed vertex of the edge is put back until the ring is simple.
+pub fn simplify_dp(ring: &[Point2D], tolerance: f64) -> Vec<Point2D> {
+    let points = open_ring(ring);
+    let n = points.len();
+    if n <= 3 {
+        return close_ring(points.to_vec());
+    }
+    let far = (1..n)
+        .max_by(|a, b| {
+            let da = points[*a].distance_2(&points[0]);
+            let db = points[*b].distance_2(&points[0]);
+            da.partial_cmp(&db).unwrap()
+        })
+        .unwrap();
+    let mut keep = vec![false; n];
+    keep[0] = true;
+    keep[far] = true;
+    dp_keep(points, 0, far, tolerance, &mut keep);
+    dp_keep(points, far, n, tolerance, &mut keep);
+    if keep.iter().filter(|k| **k).count() < 3 {
+        // Keep a triangle for the ring to have an area
+        let third = farthest_between(points, 0, far)
+            .into_iter()
+            .chain(farthest_between(points, far, n))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap();
+        keep[third.0] = true;
+    }
+
+    loop {
+        let kept: Vec<usize> = (0..n).filter(|i| keep[*i]).collect();
+        let simplified: Vec<Point2D> = kept.iter().map(|i| points[*i]).collect();
+        let crossing = crossing_edges(&simplified);
+        let mut restored = false;
+        for e in crossing {
+            let end = if e + 1 == kept.len() { n } else { kept[e + 1] };
+            if let Some((i, _)) = farthest_between(points, kept[e], end) {
+                keep[i] = true;
+                restored = true;
+            }
+        }
+        // An edge of the original ring can't be restored, the input is not simple
+        if !restored {
+            return close_ring(simplified);
+        }
+    }
+}
+
+// Visvalingam-Whyatt that preserves the topology, the original ring is kept
+// if it would collapse
+pub fn simplify_vw(ring: &[Point2D], tolerance: f64) -> Vec<Point2D> {
+    let polygon = Polygon::new(
+        LineString::from(close_ring(open_ring(ring).to_vec())),
+        vec![],
+    );
+    let simplified: Vec<Point2D> = polygon
+        .simplifyvw_preserve(&tolerance)
+        .exterior()
+        .coords()
+        .map(|c| [c.x, c.y])
+        .collect();
+    if (simplified.len() < 4) || !crossing_edges(open_ring(&simplified)).is_empty() {
+        close_ring(open_ring(ring).to_vec())
+    } else {
+        simplified
+    }
+}
+
+pub fn smooth_chaikin(ring: &[Point2D], iterations: usize) -> Vec<Point2D> {
+    let mut polygon = Polygon::new(
+        LineString::from(close_ring(open_ring(ring).to_vec())),
+        vec![],
+    );
+    for _ in 0..iterations {
+        let smoothed = polygon.chaikin_smoothing(1);
+        let points: Vec<Point2D> = smoothed.exterior().coords().map(|c| [c.x, c.y]).collect();
+        if !crossing_edges(open_ring(&points)).is_empty() {
+            break;
+        }
+        polygon = smoothed;
+    }
+    polygon.exterior().coords().map(|c| [c.x, c.y]).collect()
+}
+
+// Mark the vertices to keep between `start` and `end`, the index wraps around the ring
+fn dp_keep(points: &[Point2D], start: usize, end: usize, tolerance: f64, keep: &mut [bool]) {
+    if let Some((i, d)) = farthest_between(points, start, end) {
+        if d > tolerance {
+            keep[i] = true;
+            dp_keep(points, start, i, tolerance, keep);
+            dp_keep(points, i, end, tolerance, keep);
+        }
+    }
+}
+
+// The vertex strictly between `start` and `end` that is farthest from the edge between them
+fn farthest_between(points: &[Point2D], start: usize, end: usize) -> Option<(usize, f64)> {
+    let n = points.len();
+    let edge = SegmentLine::new(points[start % n], points[end % n]);
+    (start + 1..end)
+        .map(|i| (i % n, edge.distance_2(&points[i % n]).sqrt()))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+}
+
+// The index of the edges that touch a non-adjacent edge or fold back onto the next edge,
+// the edge `i` goes from vertex `i` to `i + 1` of the open ring
+fn crossing_edges(points: &[Point2D]) -> Vec<usize> {
+    let n = points.len();
+    let segments: Vec<IndexedSegment> = (0..n)
+        .map(|i| GeomWithData::new(SegmentLine::new(points[i], points[(i + 1) % n]), i))
+        .collect();
+    let tree = RTree::bulk_load(segments.clone());
+    let mut crossing: Vec<usize> = segments
+        .par_iter()
+        .filter(|s| {
+            let i = s.data;
+            let next = (i + 1) % n;
+            let line = Line::new(s.geom().from, s.geom().to);
+            tree.locate_in_envelope_intersecting(&s.envelope())
+                .any(|o| {
+                    let j = o.data;
+                    if j == i {
+                        return false;
+                    }
+                    if (j == next) || (i == (j + 1) % n) {
+                        return folds_back(points, i, j);
+                    }
+                    line.intersects(&Line::new(o.geom().from, o.geom().to))
+                })
+        })
+        .map(|s| s.data)
+        .collect();
+    crossing.sort_unstable();
+    crossing
+}
+
+// Two adjacent edges overlap if they are collinear and point to opposite directions
+fn folds_back(points: &[Point2D], i: usize, j: usize) -> bool {
+    let n = points.len();
+    let (a, b) = if (i + 1) % n == j { (i, j) } else { (j, i) };
+    let (p, q, r) = (points[a], points[b], points[(b + 1) % n]);
+    let (u, v) = ([q[0] - p[0], q[1] - p[1]], [r[0] - q[0], r[1] - q[1]]);
+    (u[0] * v[1] - u[1] * v[0] == 0.0) && (u[0] * v[0] + u[1] * v[1] < 0.0)
+}
+
+// The ring without the closing point
+fn open_ring(ring: &[Point2D]) -> &[Point2D] {
+    match (ring.first(), ring.last()) {
+        (Some(first), Some(last)) if (ring.len() > 1) && (first == last) => &ring[..ring.len() - 1],
+        _ => ring,
+    }
+}
+
+fn close_ring(mut ring: Vec<Point2D>) -> Vec<Point2D> {
+    ring.push(ring[0]);
+    ring
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simplify::{crossing_edges, simplify_dp, simplify_vw, smooth_chaikin};
+
+    #[test]
+    fn test_simplify() {
+        // A square with small bumps on the bottom edge
+        let ring = vec![
+            [0.0, 0.0],
+            [1.0, 0.1],
+            [2.0, 0.0],
+            [3.0, 0.1],
+            [4.0, 0.0],
+            [4.0, 4.0],
+            [0.0, 4.0],
+        ];
+        let simplified = simplify_dp(&ring, 0.5);
+        assert_eq!(
+            simplified,
+            vec![[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]]
+        );
+        assert_eq!(simplify_dp(&ring, 0.0).len(), ring.len() + 1);
+        assert_eq!(simplify_vw(&ring, 0.5).len(), 5);
+
+        // A deep notch reaching below the bottom edge, the bottom vertex is within
+        // the tolerance, but removing it makes the bottom edge cross the notch
+        let ring = vec![
+            [0.0, 10.0],
+            [0.0, 0.0],
+            [5.0, -0.4],
+            [10.0, 0.0],
+            [10.0, 10.0],
+            [6.0, 10.0],
+            [5.0, -0.2],
+            [4.0, 10.0],
+        ];
+        let simplified = simplify_dp(&ring, 0.5);
+        assert!(simplified.contains(&[5.0, -0.4]));
+        assert!(crossing_edges(&simplified[..simplified.len() - 1]).is_empty());
+
+        let smoothed = smooth_chaikin(&ring, 3);
+        assert!(smoothed.len() > ring.len());
+        assert!(crossing_edges(&smoothed[..smoothed.len() - 1]).is_empty());
+    }
+}
@@ -1,13 +1,19 @@
-use itertools::min;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
 use nalgebra_sparse::ops::serial::{spadd_csr_prealloc, spadd_pattern};
 use nalgebra_sparse::ops::Op;
 use nalgebra_sparse::CsrMatrix;
 use ndarray::parallel::prelude::*;
 use ndarray::prelude::*;
 use numpy::PyReadonlyArray2;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
-use crate::utils::zscore2pvalue;
+use crate::utils::{check_neighbors, check_not_empty, check_same_length, zscore2pvalue};
+
+type WeightsCsr = (usize, Vec<usize>, Vec<usize>, Vec<usize>, Vec<f64>);
 
 pub(crate) fn register(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(spatial_weights_sparse_matrix, m)?)?;
@@ -23,13 +29,14 @@ pub fn moran_i_parallel(
     labels: Vec<usize>,
     two_tailed: bool,
     pval: f64,
-) -> Vec<(f64, f64, f64)> {
+) -> PyResult<Vec<(f64, f64, f64)>> {
     let x: ArrayView2<f64> = x.as_array();
-    let w = SpatialWeight::from_neighbors(neighbors, labels);
-    x.outer_iter()
+    check_same_length("labels", labels.len(), "columns of x", x.ncols())?;
+    let w = SpatialWeight::from_neighbors(neighbors, labels)?;
+    Ok(x.outer_iter()
         .into_par_iter()
         .map(|row| moran_i_index(row, &w, two_tailed, pval))
-        .collect()
+        .collect())
 }
 
 #[pyfunction]
@@ -38,13 +45,14 @@ pub fn geary_c_parallel(
     neighbors: Vec<Vec<usize>>,
     labels: Vec<usize>,
     pval: f64,
-) -> Vec<(f64, f64, f64)> {
+) -> PyResult<Vec<(f64, f64, f64)>> {
     let x: ArrayView2<f64> = x.as_array();
-    let w = SpatialWeight::from_neighbors(neighbors, labels);
-    x.outer_iter()
+    check_same_length("labels", labels.len(), "columns of x", x.ncols())?;
+    let w = SpatialWeight::from_neighbors(neighbors, labels)?;
+    Ok(x.outer_iter()
         .into_par_iter()
         .map(|row| geary_c_index(row, &w, pval))
-        .collect()
+        .collect())
 }
 
 // Acquire spatial weights matrix from neighbors relationships
@@ -53,11 +61,14 @@ pub fn geary_c_parallel(
 pub fn spatial_weights_sparse_matrix(
     neighbors: Vec<Vec<usize>>,
     labels: Vec<usize>,
-) -> (usize, Vec<usize>, Vec<usize>, Vec<usize>, Vec<f64>)
+) -> PyResult<WeightsCsr>
 // (shape_n, indptr, indice (or called `col_index`), row_index, data)
 {
     let n = neighbors.len();
-    let min_offset = min(labels).unwrap();
+    check_neighbors(&neighbors, &labels)?;
+    check_not_empty("labels", n)?;
+    // The column index is the position of the label, the labels can be any unique integers
+    let columns = label_columns(&labels)?;
     let mut ptr: usize = 0;
     let mut indptr = vec![0];
     let mut indice = vec![]; // col_index
@@ -65,7 +76,7 @@ pub fn spatial_weights_sparse_matrix(
     let mut data: Vec<f64> = vec![];
     for (ix, neighs) in neighbors.into_iter().enumerate() {
         let nn = neighs.len();
-        let mut neighs: Vec<usize> = neighs.into_iter().map(|j| j - min_offset).collect();
+        let mut neighs: Vec<usize> = neighs.into_iter().map(|j| columns[&j]).collect();
         neighs.sort();
         let weights = 1.0 / (nn as f64);
         neighs.into_iter().for_each(|i| {
@@ -76,7 +87,18 @@ pub fn spatial_weights_sparse_matrix(
         ptr += nn;
         indptr.push(ptr);
     }
-    (n, indptr, indice, row_index, data)
+    Ok((n, indptr, indice, row_index, data))
+}
+
+// Map each label to its position, the labels must be unique
+pub fn label_columns<T: Copy + Eq + Hash + Display>(labels: &[T]) -> PyResult<HashMap<T, usize>> {
+    let mut columns = HashMap::with_capacity(labels.len());
+    for (ix, l) in labels.iter().enumerate() {
+        if columns.insert(*l, ix).is_some() {
+            return Err(PyValueError::new_err(format!("Duplicated label {}", l)));
+        }
+    }
+    Ok(columns)
 }
 
 #[derive(Clone)]
@@ -91,10 +113,13 @@ pub struct SpatialWeight {
 }
 
 impl SpatialWeight {
-    pub fn from_neighbors(neighbors: Vec<Vec<usize>>, labels: Vec<usize>) -> Self {
-        let (n, indptr, indice, row_index, data) = spatial_weights_sparse_matrix(neighbors, labels);
+    pub fn from_neighbors(neighbors: Vec<Vec<usize>>, labels: Vec<usize>) -> PyResult<Self> {
+        let (n, indptr, indice, row_index, data) = spatial_weights_sparse_matrix(neighbors, labels)?;
         let w_sum = data.iter().sum();
-        let w_sparse = CsrMatrix::try_from_csr_data(n, n, indptr, indice.to_owned(), data).unwrap();
+        let w_sparse = CsrMatrix::try_from_csr_data(n, n, indptr, indice.to_owned(), data)
+            .map_err(|e| {
+                PyValueError::new_err(format!("Failed to build the spatial weights matrix: {}", e))
+            })?;
         let w1_pattern = spadd_pattern(w_sparse.pattern(), w_sparse.transpose().pattern());
         let w1_len = w1_pattern.nnz();
         let mut w1 = CsrMatrix::try_from_pattern_and_values(w1_pattern, vec![0.0; w1_len]).unwrap();
@@ -114,7 +139,7 @@ impl SpatialWeight {
         let s2 = (&w_sum0 + &w_sum1).mapv(|a| a.powi(2)).sum();
         let s02 = w_sum * w_sum;
 
-        SpatialWeight {
+        Ok(SpatialWeight {
             row_index,
             col_index: indice,
             w_sum,
@@ -122,7 +147,7 @@ impl SpatialWeight {
             s2,
             s02,
             w_sparse,
-        }
+        })
     }
 
     pub fn wx_i(&self, z: Array1<f64>) -> f64 {
@@ -224,3 +249,26 @@ pub fn geary_c_index(x: ArrayView1<f64>, w: &SpatialWeight, pval: f64) -> (f64,
 
     (pattern, c_value, p_norm)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::spatial_autocorr::spatial_weights_sparse_matrix;
+
+    #[test]
+    fn test_spatial_weights_columns() {
+        // The column of a label is its position in labels, not the label minus the minimum
+        let labels = vec![10, 3, 7];
+        let neighbors = vec![vec![7, 3], vec![10], vec![10, 3]];
+        let (n, indptr, indice, row_index, data) =
+            spatial_weights_sparse_matrix(neighbors, labels).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(indptr, vec![0, 2, 3, 5]);
+        assert_eq!(indice, vec![1, 2, 0, 0, 1]);
+        assert_eq!(row_index, vec![0, 0, 1, 2, 2]);
+        assert_eq!(data, vec![0.5, 0.5, 1.0, 0.5, 0.5]);
+
+        // Duplicated labels have no column to map to
+        let result = spatial_weights_sparse_matrix(vec![vec![1], vec![1]], vec![1, 1]);
+        assert!(result.is_err());
+    }
+}
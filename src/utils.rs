@@ -2,6 +2,10 @@
 // use ndarray::{Array, Array1, ArrayView1, ArrayView2};
 // use ndarray::prelude::*;
 // use rayon::prelude::*;
+use std::collections::HashSet;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
 use statrs::distribution::{ChiSquared, ContinuousCDF, Normal};
 
 pub fn py_kwarg<T>(arg: Option<T>, default_value: T) -> T {
@@ -11,6 +15,78 @@ pub fn py_kwarg<T>(arg: Option<T>, default_value: T) -> T {
     }
 }
 
+// The inputs are validated before entering the computation,
+// so that python get a ValueError instead of a PanicException
+pub fn check_same_length(name1: &str, n1: usize, name2: &str, n2: usize) -> PyResult<()> {
+    if n1 != n2 {
+        return Err(PyValueError::new_err(format!(
+            "The length of {} ({}) does not match the length of {} ({})",
+            name1, n1, name2, n2
+        )));
+    }
+    Ok(())
+}
+
+pub fn check_points<const K: usize>(points: &[[f64; K]]) -> PyResult<()> {
+    match points.iter().position(|p| p.iter().any(|c| !c.is_finite())) {
+        Some(ix) => Err(PyValueError::new_err(format!(
+            "The point at index {} has NaN or infinite coordinates: {:?}",
+            ix, points[ix]
+        ))),
+        None => Ok(()),
+    }
+}
+
+// The collections of the parallel functions, one element per ROI
+pub fn check_collections<A, B>(c1: &[A], c2: &[B]) -> PyResult<()> {
+    if c1.len() != c2.len() {
+        return Err(PyValueError::new_err(format!(
+            "The number of ROIs in the collections does not match: {} and {}",
+            c1.len(),
+            c2.len()
+        )));
+    }
+    Ok(())
+}
+
+pub fn check_points_labels<const K: usize>(points: &[[f64; K]], labels: &[usize]) -> PyResult<()> {
+    check_same_length("points", points.len(), "labels", labels.len())?;
+    check_points(points)
+}
+
+// Every neighbor must be one of the labels, so that it can be looked up
+pub fn check_neighbors(neighbors: &[Vec<usize>], labels: &[usize]) -> PyResult<()> {
+    check_same_length("neighbors", neighbors.len(), "labels", labels.len())?;
+    let labels_set: HashSet<&usize> = labels.iter().collect();
+    for (neighs, l) in neighbors.iter().zip(labels) {
+        if let Some(n) = neighs.iter().find(|n| !labels_set.contains(n)) {
+            return Err(PyValueError::new_err(format!(
+                "The neighbor {} of cell {} is not found in labels",
+                n, l
+            )));
+        }
+    }
+    Ok(())
+}
+
+// A polygon ring needs at least 3 points, the ring is closed automatically
+pub fn check_polygon(polygon: &[[f64; 2]]) -> PyResult<()> {
+    if polygon.len() < 3 {
+        return Err(PyValueError::new_err(format!(
+            "A polygon must have at least 3 points, got {}",
+            polygon.len()
+        )));
+    }
+    check_points(polygon)
+}
+
+pub fn check_not_empty(name: &str, n: usize) -> PyResult<()> {
+    if n == 0 {
+        return Err(PyValueError::new_err(format!("The {} is empty", name)));
+    }
+    Ok(())
+}
+
 pub fn zscore2pvalue(z: f64, two_tailed: bool) -> f64 {
     let norm_dist: Normal = Normal::new(0.0, 1.0).unwrap(); // follow the scipy's default
     let mut p: f64 = if z > 0.0 {
@@ -69,4 +145,17 @@ pub fn chisquare2pvalue(chi2_value: f64, ddof: f64) -> f64 {
 // }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use crate::utils::{check_neighbors, check_points, check_same_length};
+
+    #[test]
+    fn test_check_inputs() {
+        assert!(check_same_length("points", 2, "labels", 2).is_ok());
+        assert!(check_same_length("points", 2, "labels", 3).is_err());
+        assert!(check_points(&[[0.0, 1.0], [1.0, 2.0]]).is_ok());
+        assert!(check_points(&[[0.0, 1.0], [f64::NAN, 2.0]]).is_err());
+        assert!(check_points(&[[0.0, f64::INFINITY, 1.0]]).is_err());
+        assert!(check_neighbors(&[vec![0, 1], vec![1]], &[0, 1]).is_ok());
+        assert!(check_neighbors(&[vec![0, 2], vec![1]], &[0, 1]).is_err());
+    }
+}
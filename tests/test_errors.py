@@ -0,0 +1,50 @@
+import numpy as np
+import pytest
+from spatialtis_core import points_neighbors, spatial_weight, neighbor_components, reads_wkt_points, fast_corr, \
+    neighbors_symmetric
+from spatialtis_core.neighbors import points_neighbors_parallel
+
+points = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]]
+labels = [0, 1, 2, 3]
+
+
+def test_points_labels_mismatch():
+    with pytest.raises(ValueError, match="labels"):
+        points_neighbors(points, [0, 1, 2], r=1.0, method="kdtree")
+
+
+def test_non_finite_points():
+    with pytest.raises(ValueError, match="finite"):
+        points_neighbors([[0.0, 0.0], [np.nan, 1.0]], [0, 1], r=1.0, method="kdtree")
+
+
+def test_missing_neighbor_label():
+    neighbors = [[1], [0, 5]]
+    with pytest.raises(ValueError, match="5"):
+        spatial_weight(neighbors, [0, 1])
+    with pytest.raises(ValueError, match="5"):
+        neighbor_components(neighbors, [0, 1], ["a", "b"])
+
+
+def test_invalid_wkt():
+    with pytest.raises(ValueError):
+        reads_wkt_points(["POINT(1 2", "POINT(0 2)"])
+    with pytest.raises(TypeError):
+        reads_wkt_points(["POLYGON((0 1,0 2,1 1,0 1))"])
+
+
+def test_corr_shape_mismatch():
+    with pytest.raises(ValueError, match="shape"):
+        fast_corr(np.random.rand(3, 10), np.random.rand(4, 10))
+
+
+def test_invalid_mode():
+    with pytest.raises(ValueError, match="mode"):
+        neighbors_symmetric([[0], [1]], [0, 1], mode="unknown")
+
+
+def test_unknown_method():
+    with pytest.raises(ValueError, match="relativ"):
+        points_neighbors(points, labels, method="relativ")
+    with pytest.raises(ValueError, match="relativ"):
+        points_neighbors_parallel([points], [labels], method="relativ")
@@ -1,4 +1,8 @@
-from spatialtis_core import points_bbox, multipoints_bbox, polygons_area, multipolygons_area, points_shapes
+import pytest
+from spatialtis_core import points_bbox, multipoints_bbox, polygons_area, multipolygons_area, points_shapes, \
+    voronoi_shapes, polygons_morphology, polygons_centroid, polygons_interior_point, \
+    points_in_regions, points_signed_distance, dumps_polygons_wkt, reads_wkt_polygons, reads_wkt_multipolygons, \
+    convex_hull_3d, hull_volume_area, polygons_simplify, polygons_smooth
 
 points = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.5, 0.5]]
 rect = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]]
@@ -36,3 +40,92 @@ def test_points_shapes_concave():
     shape = shape[0]
     for i in shape:
         assert i in rect
+
+
+def test_voronoi_shapes():
+    cells = voronoi_shapes(points)
+    assert len(cells) == len(points)
+    assert abs(sum(multipolygons_area(cells)) - 1.0) < 1e-9
+    cells = voronoi_shapes(points, roi=[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0]])
+    assert len(cells) == len(points)
+    assert abs(sum(multipolygons_area([c for c in cells if c])) - 0.5) < 1e-9
+
+
+def test_polygons_morphology():
+    table = polygons_morphology([rect, [[0.0, 0.0], [4.0, 0.0], [4.0, 2.0], [0.0, 2.0]]])
+    assert table.shape == (2, 12)
+    assert table["area"].tolist() == [1.0, 8.0]
+    assert table["perimeter"].tolist() == [4.0, 12.0]
+    assert abs(table["solidity"][1] - 1.0) < 1e-9
+    assert table["major_axis_length"][1] > table["minor_axis_length"][1]
+
+
+def test_polygons_centroid():
+    u_shape = [[0.0, 0.0], [3.0, 0.0], [3.0, 3.0], [2.0, 3.0], [2.0, 1.0], [1.0, 1.0], [1.0, 3.0], [0.0, 3.0]]
+    centroids = polygons_centroid([rect, u_shape])
+    assert centroids[0] == [0.5, 0.5]
+    x, y = polygons_interior_point([u_shape])[0]
+    assert (x < 1.0) | (x > 2.0) | (y < 1.0)
+    donut = [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+             [[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]]]
+    assert polygons_centroid([donut, rect]) == [[2.0, 2.0], [0.5, 0.5]]
+    x, y = polygons_interior_point([donut])[0]
+    assert (x < 1.0) | (x > 3.0)
+
+
+def test_points_in_regions():
+    donut = [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+             [[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]]]
+    square = [[4.0, 0.0], [6.0, 0.0], [6.0, 2.0], [4.0, 2.0]]
+    regions = points_in_regions([[0.5, 0.5], [2.0, 2.0], [4.0, 1.0], [5.0, 1.0]], [donut, square])
+    assert regions == [[0], [], [0, 1], [1]]
+
+
+def test_points_signed_distance():
+    donut = [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+             [[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]]]
+    distances, bands = points_signed_distance([[0.5, 2.0], [2.0, 2.0], [6.0, 2.0]], [donut], bands=[-1.0, 0.0, 1.0])
+    assert distances == [-0.5, 1.0, 2.0]
+    assert bands == [1, 3, 3]
+
+
+def test_polygons_with_holes():
+    donut = [[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]],
+             [[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]]]
+    square = [[5.0, 0.0], [6.0, 0.0], [6.0, 1.0], [5.0, 1.0]]
+    assert polygons_area(donut) == 12.0
+    assert multipolygons_area([rect, donut, [donut, [square]]]) == [1.0, 12.0, 13.0]
+    assert multipoints_bbox([donut, [donut, [square]]]) == [(0.0, 0.0, 4.0, 4.0), (0.0, 0.0, 6.0, 4.0)]
+
+    wkt = dumps_polygons_wkt([donut])
+    assert wkt[0].startswith("POLYGON")
+    assert len(reads_wkt_polygons(wkt, holes=True)[0]) == 2
+    wkt = dumps_polygons_wkt([donut, [donut, [square]]])
+    assert all(w.startswith("MULTIPOLYGON") for w in wkt)
+    assert [len(mp) for mp in reads_wkt_multipolygons(wkt)] == [1, 2]
+
+
+def test_convex_hull_3d():
+    cube = [[x, y, z] for x in [0.0, 2.0] for y in [0.0, 2.0] for z in [0.0, 2.0]] + [[1.0, 1.0, 1.0]]
+    faces = convex_hull_3d([cube])[0]
+    assert all(8 not in f for f in faces)
+    volume, area = hull_volume_area([cube, cube[:3]])[0]
+    assert abs(volume - 8.0) < 1e-9
+    assert abs(area - 24.0) < 1e-9
+    assert hull_volume_area([cube[:3]])[0] == (0.0, 0.0)
+
+
+def test_polygons_simplify():
+    bumpy = [[0.0, 0.0], [1.0, 0.1], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]
+    simplified = polygons_simplify([bumpy], 0.5)
+    assert simplified[0] == [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0], [0.0, 0.0]]
+    simplified = polygons_simplify([bumpy], 0.5, method="vw")
+    assert polygons_area(simplified[0]) == 4.0
+
+
+def test_polygons_smooth():
+    smoothed = polygons_smooth([rect], iterations=2)
+    assert len(smoothed[0]) == 4 * 4 + 1
+    assert polygons_area(smoothed[0]) < 1.0
+    with pytest.raises(ValueError, match="iterations"):
+        polygons_smooth([rect], iterations=40)
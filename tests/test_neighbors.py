@@ -1,5 +1,8 @@
 import numpy as np
-from spatialtis_core import points_neighbors, bbox_neighbors, neighbor_components, spatial_weight
+import pytest
+from spatialtis_core import points_neighbors, bbox_neighbors, neighbor_components, spatial_weight, KDTree2D, \
+    points_neighbors_bipartite, points_neighbors_csr, spatial_weight_csr, polygons_neighbors, \
+    voronoi_neighbors
 
 points = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]]
 points3d = [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]]
@@ -28,6 +31,33 @@ def test_kd_tree_search_k_and_r():
         assert set(n) == set(r)
 
 
+def test_kd_tree_search_distance():
+    neighbors, distances = points_neighbors(points, labels, k=3, method="kdtree", return_distance=True)
+    for n, d in zip(neighbors, distances):
+        assert len(n) == len(d)
+        assert sorted(d) == [0.0, 1.0, 1.0]
+
+
+def test_kd_tree_object():
+    tree = KDTree2D(points, labels)
+    assert tree.size == 4
+    results = [[0, 1, 3], [0, 1, 2], [1, 2, 3], [0, 2, 3]]
+    for n, r in zip(tree.within(points, 1.0), results):
+        assert set(n) == set(r)
+    for n, r in zip(tree.knn(points, 3), results):
+        assert set(n) == set(r)
+    for n, r in zip(tree.knn_within(points, 10.0, 3), results):
+        assert set(n) == set(r)
+    neighbors, distances = tree.query_with_dist([[0.5, 0.0]], 0.6, 0)
+    assert set(neighbors[0]) == {0, 3}
+
+
+def test_kd_tree_search_bipartite():
+    neighbors = points_neighbors_bipartite([[0.0, 0.5], [5.0, 5.0]], points, [10, 11, 12, 13], r=0.6)
+    assert set(neighbors[0]) == {10, 11}
+    assert neighbors[1] == []
+
+
 def test_kd_tree_search_3d():
     neighbors = points_neighbors(points3d, labels, r=1.1, k=3, method="kdtree")
     results = [[0, 1, 3], [0, 1, 2], [1, 2, 3], [0, 2, 3]]
@@ -42,6 +72,34 @@ def test_delaunay():
         assert set(n) == set(r)
 
 
+def test_gabriel_relative():
+    gabriel = points_neighbors(points, labels, method="gabriel")
+    relative = points_neighbors(points, labels, method="relative")
+    results = [[0, 1, 3], [0, 1, 2], [1, 2, 3], [0, 2, 3]]
+    for g, rn, r in zip(gabriel, relative, results):
+        # The diagonal lies on the circle, it's still a gabriel edge
+        assert set(r) <= set(g)
+        assert set(rn) == set(r)
+
+
+def test_delaunay_3d():
+    cube = [[x, y, z] for x in [0.0, 1.0] for y in [0.0, 1.0] for z in [0.0, 1.0]] + [[0.5, 0.5, 0.5]]
+    neighbors = points_neighbors(cube, [i for i in range(9)], method="delaunay")
+    assert set(neighbors[8]) == set(range(9))
+    with pytest.raises(ValueError):
+        points_neighbors(cube + [[1.0, 1.0, 1.0]], [i for i in range(10)], method="delaunay")
+    with pytest.raises(ValueError):
+        points_neighbors(cube, [i for i in range(9)], method="gabriel")
+
+
+def test_delaunay_pruned():
+    far_points = points + [[10.0, 0.5]]
+    neighbors = points_neighbors(far_points, labels + [4], method="delaunay", max_edge=2.0)
+    assert neighbors[4] == [4]
+    for n in neighbors[:4]:
+        assert 4 not in n
+
+
 def test_bbox_neighbors():
     neighbors = bbox_neighbors(bboxs, labels, expand=3)
     results = [[0, 1], [0, 1]]
@@ -67,3 +125,71 @@ def test_spatial_weight():
                        [0, 1 / 3, 1 / 3, 1 / 3],
                        [1 / 3, 0, 1 / 3, 1 / 3]])
     assert np.array_equal(A, result)  # If it matches, all elements should be 0.0
+    # Labels that are not consecutive are mapped to their position
+    sparse_labels = [10, 30, 20, 40]
+    neighbors = points_neighbors(points, sparse_labels, k=3, method="kdtree")
+    assert np.array_equal(spatial_weight(neighbors, sparse_labels).toarray(), result)
+
+
+def test_neighbors_csr():
+    indptr, indices, distances = points_neighbors_csr(np.array(points), np.array(labels), k=3, return_distance=True)
+    results = [[0, 1, 3], [0, 1, 2], [1, 2, 3], [0, 2, 3]]
+    for i, r in enumerate(results):
+        assert set(indices[indptr[i]:indptr[i + 1]]) == set(r)
+    assert len(distances) == len(indices)
+    A = spatial_weight_csr(indptr, indices, labels).toarray()
+    B = spatial_weight(points_neighbors(points, labels, k=3), labels).toarray()
+    assert np.array_equal(A, B)
+    with pytest.raises(ValueError):
+        spatial_weight_csr(indptr, indices + 10, labels)
+    # A slice of rows that doesn't start from 0, the last label is not a neighbor of any cell
+    rows = spatial_weight_csr(np.array([3, 4, 5]), np.array([7, 7, 7, 2, 2]), [2, 3])
+    assert rows.shape == (2, 2)
+    assert np.array_equal(rows.toarray(), [[1.0, 0.0], [1.0, 0.0]])
+    with pytest.raises(ValueError):
+        points_neighbors_csr(np.array(points), method="kdree")
+
+
+def test_neighbors_grid_csr():
+    rng = np.random.default_rng(0)
+    data = rng.uniform(0, 100, (2000, 2))
+    kd_indptr, kd_indices, _ = points_neighbors_csr(data, r=5.0, method="kdtree")
+    for tile_size in [None, 5.0, 17.0]:
+        indptr, indices, distances = points_neighbors_csr(data, r=5.0, method="grid", tile_size=tile_size,
+                                                          return_distance=True)
+        assert np.array_equal(indptr, kd_indptr)
+        for i in range(len(data)):
+            assert set(indices[indptr[i]:indptr[i + 1]]) == set(kd_indices[kd_indptr[i]:kd_indptr[i + 1]])
+        assert np.all(distances <= 5.0)
+
+
+def test_polygons_neighbors():
+    polygons = [[(0.0, 0.0), (10.0, 0.0), (10.0, 1.0), (0.0, 1.0)],
+                [(2.0, 1.0), (6.0, 1.0), (6.0, 2.0), (2.0, 2.0)],
+                [(0.0, 2.5), (10.0, 2.5), (10.0, 3.5), (0.0, 3.5)]]
+    neighbors = polygons_neighbors(polygons, [0, 1, 2])
+    assert neighbors == [[0, 1], [0, 1], [2]]
+    neighbors, border = polygons_neighbors(polygons, [0, 1, 2], buffer=0.5, return_border=True)
+    assert neighbors == [[0, 1], [0, 1, 2], [1, 2]]
+    assert border[0][0] == 0.0
+    assert abs(border[0][1] - 4.0) < 1e-6
+    assert abs(border[1][2] - 4.0) < 1e-6
+
+
+def test_adaptive_radius():
+    points_a = [[0.0, 0.0], [3.0, 0.0], [3.5, 0.0]]
+    radius = [4.0, 1.0, 1.0]
+    neighbors = points_neighbors(points_a, [0, 1, 2], r=radius)
+    assert neighbors == [[0, 1, 2], [1, 2, 0], [2, 1, 0]]
+    neighbors = points_neighbors(points_a, [0, 1, 2], r=radius, radius_mode="both")
+    assert neighbors == [[0], [1, 2], [2, 1]]
+
+
+def test_voronoi_neighbors():
+    neighbors, border = voronoi_neighbors(points, labels)
+    for n, b in zip(neighbors, border):
+        assert len(n) == len(b) == 3
+        assert sorted(b) == [0.0, 0.5, 0.5]
+    roi = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0]]
+    neighbors, border = voronoi_neighbors(points, labels, roi=roi)
+    assert set(neighbors[2]) == {2}
@@ -0,0 +1,31 @@
+from spatialtis_core import neighbors_symmetric, neighbors_remove_self, neighbors_cap_degree, neighbors_khop, \
+    neighbors_khop_with_hops
+
+neighbors = [[10, 11], [11, 12], [12]]
+labels = [10, 11, 12]
+
+
+def test_neighbors_symmetric():
+    union = neighbors_symmetric(neighbors, labels)
+    assert [set(n) for n in union] == [{10, 11}, {10, 11, 12}, {11, 12}]
+    mutual = neighbors_symmetric(neighbors, labels, mode="intersection")
+    assert mutual == [[10], [11], [12]]
+
+
+def test_neighbors_remove_self():
+    assert neighbors_remove_self(neighbors, labels) == [[11], [12], []]
+
+
+def test_neighbors_cap_degree():
+    assert neighbors_cap_degree(neighbors, 1) == [[10], [11], [12]]
+    assert neighbors_cap_degree(neighbors, 1, [[1.0, 0.5], [0.0, 1.0], [0.0]]) == [[11], [11], [12]]
+
+
+def test_neighbors_khop():
+    chain = [[1], [0, 2], [1, 3], [2]]
+    chain_labels = [0, 1, 2, 3]
+    assert neighbors_khop(chain, chain_labels, 1) == [[1], [0, 2], [1, 3], [2]]
+    assert neighbors_khop(chain, chain_labels, 2)[0] == [1, 2]
+    khop, hops = neighbors_khop_with_hops(chain, chain_labels, 3)
+    assert khop[0] == [1, 2, 3]
+    assert hops[0] == [1, 2, 3]
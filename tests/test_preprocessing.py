@@ -0,0 +1,66 @@
+import numpy as np
+import pytest
+from spatialtis_core import mask2cell, mask_contours, cell_intensity, rasterize_polygons, polygons_area
+
+mask = np.array([[0, 1, 1, 0],
+                 [0, 1, 1, 2],
+                 [3, 0, 0, 2]])
+
+
+def test_mask2cell():
+    cells = mask2cell(mask)
+    assert cells["label"].tolist() == [1, 2, 3]
+    assert cells["area"].tolist() == [4, 2, 1]
+    assert cells["centroid"][0] == [1.5, 0.5]
+    assert cells["bbox"][1] == (3.0, 1.0, 3.0, 2.0)
+    assert cells["eccentricity"][2] == 0.0
+    assert polygons_area(cells["border"][0]) == 4.0
+    # A label in two pieces touching by a corner is one cell with a part for each piece
+    two_pieces = np.array([[0, 0, 0, 1],
+                           [1, 1, 1, 0],
+                           [1, 0, 0, 0]])
+    cells = mask2cell(two_pieces)
+    assert cells["label"].tolist() == [1]
+    assert cells["area"].tolist() == [5]
+    assert len(cells["border"][0]) == 2
+    assert polygons_area(cells["border"][0]) == 5.0
+
+
+def test_mask_contours():
+    labels, contours = mask_contours(mask)
+    assert labels == [1, 2, 3]
+    assert contours[0] == [[[[0.5, -0.5], [2.5, -0.5], [2.5, 1.5], [0.5, 1.5], [0.5, -0.5]]]]
+    labels, contours = mask_contours(mask, subpixel=True)
+    assert polygons_area(contours[2]) == 0.5
+    labels, contours = mask_contours(mask, tolerance=0.5)
+    assert len(contours[0][0][0]) == 5
+
+
+def test_cell_intensity():
+    image = np.stack([np.arange(12, dtype=float).reshape(3, 4), np.ones((3, 4))])
+    tables = cell_intensity(mask, image, channels=["a", "b"], percentiles=[25])
+    assert tables["sum"].loc[1, "a"] == 1 + 2 + 5 + 6
+    assert tables["mean"].loc[2, "b"] == 1.0
+    assert tables["max"].loc[3, "a"] == 8.0
+    assert tables["p25"].loc[1, "a"] == np.percentile([1, 2, 5, 6], 25)
+    # All pixels of these cells are on the border
+    tables = cell_intensity(mask, image, exclude_border=True)
+    assert tables["mean"].isna().all().all()
+    # A label in two pieces is one row, same as the cells of mask2cell
+    two_pieces = np.array([[0, 0, 0, 1],
+                           [1, 1, 1, 0],
+                           [1, 0, 2, 2]])
+    tables = cell_intensity(two_pieces, image)
+    cells = mask2cell(two_pieces)
+    assert tables["sum"].index.tolist() == cells["label"].tolist() == [1, 2]
+    assert tables["sum"].loc[1, "a"] == 3 + 4 + 5 + 6 + 8
+
+
+def test_rasterize_polygons():
+    cells = mask2cell(mask)
+    rendered = rasterize_polygons(cells["border"].tolist(), mask.shape, labels=cells["label"].tolist())
+    assert np.array_equal(rendered, mask)
+
+    square = [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]
+    with pytest.raises(ValueError):
+        rasterize_polygons([square, square], (3, 3), overlap="error")